@@ -7,6 +7,11 @@ fn main() {
     let project_name = env::var("CARGO_PKG_NAME").unwrap();
     let mut config: cbindgen::Config = Default::default();
     config.language = cbindgen::Language::C;
+    // the functions behind a feature are only declared when it is compiled in
+    config.defines.insert(String::from("feature = prometheus"), String::from("RUST_PY_PROMETHEUS"));
+    if env::var_os("CARGO_FEATURE_PROMETHEUS").is_some() {
+        config.after_includes = Some(String::from("#define RUST_PY_PROMETHEUS"));
+    }
     cbindgen::generate_with_config(&crate_dir, config)
         .unwrap()
         .write_to_file(format!("target/{}.h", project_name));
@@ -4,13 +4,17 @@
 # vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
 
 __all__ = ["PyLogLevel", "PyLogFormat", "PyLogTimeFormat", "PyLogger", "PyClient", "PyClientHandle", "PyRuntime",
-           "PyMessage", "PyEvent", "PyEventType", "PyRunner", "__backend_version__"]
+           "PyMessage", "PyEvent", "PyEventType", "PyRunner", "PyFraming", "PyTransportConfig",
+           "PySignal", "PyTopicEvent", "PyTopicServer", "PyDeadLetter", "PyDeadLetters", "PyMetricsSnapshot",
+           "PyLogRecord", "PyLogOverflow", "PyLogSink", "PyLogHandler",
+           "__backend_version__"]
 
 __version__ = "1.0.0"
 
 # @formatter:off
 from .utils             import __backend_version__                                  # noqa
 from .logging           import PyLogLevel, PyLogFormat, PyLogTimeFormat, PyLogger   # noqa
+from .logging           import PyLogOverflow, PyLogSink, PyLoggingScope             # noqa
 from .client            import PyClient                                             # noqa
 from .message           import PyMessage                                            # noqa
 from .runtime           import PyRuntime                                            # noqa
@@ -18,6 +22,13 @@ from .runner            import PyRunner
 from .event_type        import PyEventType                                          # noqa
 from .event             import PyEvent                                              # noqa
 from .client_handle     import PyClientHandle                                       # noqa
+from .transport         import PyFraming, PyTransportConfig                         # noqa
+from .signals           import PySignal                                             # noqa
+from .topic             import PyTopicEvent, PyTopicServer                          # noqa
+from .dead_letter       import PyDeadLetter, PyDeadLetters                          # noqa
+from .metrics           import PyMetricsSnapshot                                    # noqa
+from .log_record        import PyLogRecord                                          # noqa
+from .log_handler       import PyLogHandler                                         # noqa
 # @formatter:on
 
 if __backend_version__ != __version__:
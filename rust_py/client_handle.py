@@ -28,3 +28,16 @@ class PyClientHandle(Structure):
         res = int(recv_count[0])
         lib.destroy_pointer(recv_count)
         return res
+
+    @property
+    def source(self):
+        # type: (PyClientHandle) -> int
+        """
+        Id carried by the events of this client
+
+        :raise: Exception if the client does not feed a runner
+        """
+        source = handle_error(lib.client_handle_source(self.inner))
+        res = int(source[0])
+        lib.destroy_pointer(source)
+        return res
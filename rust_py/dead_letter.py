@@ -0,0 +1,76 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+from .event import PyEvent
+from .utils import lib, Structure, ffi
+
+
+class PyDeadLetters(Structure):
+    """
+    Owns an array of dead letters returned by the runner, the letters are only valid while it is alive
+    """
+
+    def __init__(self, array):
+        # type: (PyDeadLetters,POINTER(FFIArray_FFIDeadLetter)) -> PyDeadLetters
+        super(PyDeadLetters, self).__init__()
+        self.__array = array
+        self.__letters = [PyDeadLetter(self, array[0].ptr[i]) for i in range(array[0].len)]
+
+    @property
+    def letters(self):
+        # type: (PyDeadLetters) -> List[PyDeadLetter]
+        return self.__letters
+
+    def __iter__(self):
+        return iter(self.__letters)
+
+    def __len__(self):
+        return len(self.__letters)
+
+    def __del__(self):
+        lib.destroy_dead_letters(self.__array)
+
+
+class PyDeadLetter(Structure):
+    def __init__(self, owner, letter):
+        # type: (PyDeadLetter,PyDeadLetters,FFIDeadLetter) -> PyDeadLetter
+        super(PyDeadLetter, self).__init__()
+        # keeps the array alive as long as the letter
+        self.__owner = owner
+        self.__letter = letter
+
+    @property
+    def subscriber(self):
+        # type: (PyDeadLetter) -> int
+        """
+        Id of the subscriber which failed, in the order of subscription starting at 1
+        """
+        return int(self.__letter.subscriber)
+
+    @property
+    def attempts(self):
+        # type: (PyDeadLetter) -> int
+        return int(self.__letter.attempts)
+
+    @property
+    def failed_at(self):
+        # type: (PyDeadLetter) -> float
+        """
+        When the last attempt failed, in seconds since the Unix epoch
+        """
+        return self.__letter.failed_at_ns / 1e9
+
+    @property
+    def error(self):
+        # type: (PyDeadLetter) -> str
+        return ffi.string(self.__letter.error.ptr, self.__letter.error.len).decode("utf-8")
+
+    @property
+    def event(self):
+        # type: (PyDeadLetter) -> PyEvent
+        return PyEvent(ffi.addressof(self.__letter, "envelope"))
+
+    def __repr__(self):
+        return "DeadLetter(subscriber={}, attempts={}, error={}, event={})".format(
+            self.subscriber, self.attempts, self.error, repr(self.event))
@@ -2,22 +2,77 @@
 # -*- coding: utf-8 -*-
 
 # vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
-from .utils import lib, Structure, PyString, ffi
+from .utils import lib, Structure, PyString, ffi, handle_error
+from .signals import PySignal
 
 
 class PyEvent(Structure):
-    def __init__(self, event):
-        # type: (PyEvent, POINTER(FFISEvent)) -> PyEvent
+    def __init__(self, envelope):
+        # type: (PyEvent, POINTER(FFISEnvelope)) -> PyEvent
         super(PyEvent, self).__init__()
         # if type(event) != _native__ffi._cffi_backend._CDataBase:
-        if str(type(event)) not in ["<type '_cffi_backend._CDataBase'>", "<type '_cffi_backend.CData'>"]:
-            raise Exception("Not a valid pointer, got {}".format(type(event)))
-        self.__inner = event
+        if str(type(envelope)) not in ["<type '_cffi_backend._CDataBase'>", "<type '_cffi_backend.CData'>"]:
+            raise Exception("Not a valid pointer, got {}".format(type(envelope)))
+        self.__envelope = envelope
 
     @property
     def inner(self):
-        # type: (PyEvent) -> POINTER(FFISEvent)
-        return self.__inner
+        # type: (PyEvent) -> FFISEvent
+        return self.__envelope.event
+
+    @property
+    def source(self):
+        # type: (PyEvent) -> int
+        """
+        Id of the source which produced the event, 0 for the client the runner was created with
+        """
+        return int(self.__envelope.source)
+
+    @property
+    def id(self):
+        # type: (PyEvent) -> int
+        """
+        Unique id of the event within its runner, to deduplicate events
+        """
+        return int(self.__envelope.id)
+
+    @property
+    def sequence(self):
+        # type: (PyEvent) -> int
+        """
+        Position of the event among the events of its type, starting at 1
+        """
+        return int(self.__envelope.sequence)
+
+    @property
+    def timestamp(self):
+        # type: (PyEvent) -> float
+        """
+        When the event entered the runner, in seconds since the Unix epoch
+        """
+        return self.__envelope.timestamp_ns / 1e9
+
+    @property
+    def trace_id(self):
+        # type: (PyEvent) -> Optional[str]
+        """
+        Trace started when the message behind the event was sent, as 32 hexadecimal digits, None when not
+        traced such as the replies of the socket, WebSocket and process clients
+        """
+        if self.__envelope.trace_id_high == 0 and self.__envelope.trace_id_low == 0:
+            return None
+        return "{:016x}{:016x}".format(self.__envelope.trace_id_high, self.__envelope.trace_id_low)
+
+    @property
+    def traceparent(self):
+        # type: (PyEvent) -> Optional[str]
+        """
+        W3C traceparent header continuing the trace of the event, None when not traced
+        """
+        trace_id = self.trace_id
+        if trace_id is None:
+            return None
+        return "00-{}-{:016x}-01".format(trace_id, self.__envelope.span_id)
 
     def is_event_1(self):
         return self.inner.tag == lib.Event1
@@ -28,6 +83,63 @@ class PyEvent(Structure):
     def is_kill(self):
         return self.inner.tag == lib.Kill
 
+    def is_connected(self):
+        return self.inner.tag == lib.Connected
+
+    def is_disconnected(self):
+        return self.inner.tag == lib.Disconnected
+
+    def is_reconnecting(self):
+        return self.inner.tag == lib.Reconnecting
+
+    def is_stdout(self):
+        return self.inner.tag == lib.ProcessStdout
+
+    def is_stderr(self):
+        return self.inner.tag == lib.ProcessStderr
+
+    def is_exited(self):
+        return self.inner.tag == lib.ProcessExited
+
+    def is_file_created(self):
+        return self.inner.tag == lib.FileCreated
+
+    def is_file_modified(self):
+        return self.inner.tag == lib.FileModified
+
+    def is_file_removed(self):
+        return self.inner.tag == lib.FileRemoved
+
+    def is_signal(self):
+        return self.inner.tag == lib.Signal
+
+    def is_subscriber_suspended(self):
+        return self.inner.tag == lib.SubscriberSuspended
+
+    @property
+    def exit_code(self):
+        # type: (PyEvent) -> Optional[int]
+        if self.is_exited():
+            return int(self.inner.process_exited)
+        return None
+
+    @property
+    def signal(self):
+        # type: (PyEvent) -> Optional[PySignal]
+        if self.is_signal():
+            return PySignal(self.inner.signal)
+        return None
+
+    def set_payload(self, payload):
+        # type: (PyEvent, str) -> None
+        """
+        Replace the payload, only from an interceptor
+
+        :raise: Exception with a string if the event has no payload
+        """
+        payload = ffi.new('char[]', payload.encode("utf-8"))
+        assert handle_error(lib.envelope_set_payload(self.__envelope, payload)) is None
+
     def __repr__(self):
         if self.is_event_1():
             return ffi.string(self.inner.event1.ptr, self.inner.event1.len)
@@ -35,5 +147,27 @@ class PyEvent(Structure):
             return ffi.string(self.inner.event2.ptr, self.inner.event2.len)
         elif self.is_kill():
             return "Kill"
+        elif self.is_connected():
+            return "Connected " + ffi.string(self.inner.connected.ptr, self.inner.connected.len)
+        elif self.is_disconnected():
+            return "Disconnected " + ffi.string(self.inner.disconnected.ptr, self.inner.disconnected.len)
+        elif self.is_reconnecting():
+            return "Reconnecting " + ffi.string(self.inner.reconnecting.ptr, self.inner.reconnecting.len)
+        elif self.is_stdout():
+            return ffi.string(self.inner.process_stdout.ptr, self.inner.process_stdout.len)
+        elif self.is_stderr():
+            return ffi.string(self.inner.process_stderr.ptr, self.inner.process_stderr.len)
+        elif self.is_exited():
+            return "Exited " + str(self.exit_code)
+        elif self.is_file_created():
+            return "Created " + ffi.string(self.inner.file_created.ptr, self.inner.file_created.len)
+        elif self.is_file_modified():
+            return "Modified " + ffi.string(self.inner.file_modified.ptr, self.inner.file_modified.len)
+        elif self.is_file_removed():
+            return "Removed " + ffi.string(self.inner.file_removed.ptr, self.inner.file_removed.len)
+        elif self.is_signal():
+            return "Signal " + self.signal.name
+        elif self.is_subscriber_suspended():
+            return "Suspended " + ffi.string(self.inner.subscriber_suspended.ptr, self.inner.subscriber_suspended.len)
         else:
             return "Unknown"
@@ -27,3 +27,58 @@ class PyEventType(Structure):
     def type_2(cls):
         # type: (PyEventType) -> PyEventType
         return cls(lib.EventType2)
+
+    @classmethod
+    def connected(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeConnected)
+
+    @classmethod
+    def disconnected(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeDisconnected)
+
+    @classmethod
+    def reconnecting(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeReconnecting)
+
+    @classmethod
+    def stdout(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeProcessStdout)
+
+    @classmethod
+    def stderr(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeProcessStderr)
+
+    @classmethod
+    def exited(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeProcessExited)
+
+    @classmethod
+    def file_created(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeFileCreated)
+
+    @classmethod
+    def file_modified(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeFileModified)
+
+    @classmethod
+    def file_removed(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeFileRemoved)
+
+    @classmethod
+    def signal(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeSignal)
+
+    @classmethod
+    def subscriber_suspended(cls):
+        # type: (PyEventType) -> PyEventType
+        return cls(lib.EventTypeSubscriberSuspended)
@@ -0,0 +1,38 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+from __future__ import absolute_import
+
+import logging
+
+from .logging import PyLogger, PyLogLevel
+
+
+class PyLogHandler(logging.Handler):
+    """
+    Handler of the standard logging module sending the records to the configured Rust logger, so they get its format,
+    filters and sinks. The logger name becomes the target with dots replaced by "::" and the fields given as
+    extra={"fields": {...}} are kept. Do not use it with a PyLogger callback which logs to this handler
+    """
+
+    def emit(self, record):
+        # type: (PyLogHandler,logging.LogRecord) -> None
+        try:
+            fields = getattr(record, "fields", None)
+            PyLogger.log(self.level_of(record.levelno), record.name.replace(".", "::"), self.format(record), fields)
+        except Exception:
+            self.handleError(record)
+
+    @staticmethod
+    def level_of(levelno):
+        # type: (int) -> PyLogLevel
+        if levelno >= logging.ERROR:
+            return PyLogLevel.Error
+        if levelno >= logging.WARNING:
+            return PyLogLevel.Warn
+        if levelno >= logging.INFO:
+            return PyLogLevel.Info
+        if levelno >= logging.DEBUG:
+            return PyLogLevel.Debug
+        return PyLogLevel.Trace
@@ -0,0 +1,72 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+from __future__ import absolute_import
+
+import logging
+
+from .utils import lib, Structure, ffi
+
+
+def _text(array):
+    if array.len == 0:
+        return ""
+    return ffi.string(ffi.cast("char *", array.ptr), array.len).decode("utf-8", "replace")
+
+
+# levels of PyLogLevel in the order Trace, Debug, Info, Warn, Error
+_LOGGING_LEVELS = [5, logging.DEBUG, logging.INFO, logging.WARNING, logging.ERROR]
+
+
+class PyLogRecord(Structure):
+    """
+    Structured log event given to a PyLogger created with structured=True, everything is copied out of the native
+    record which is freed right away
+    """
+
+    def __init__(self, record):
+        # type: (PyLogRecord,POINTER(FFILogRecord)) -> PyLogRecord
+        super(PyLogRecord, self).__init__()
+        inner = record[0]
+        self.level = int(inner.level)
+        self.created = inner.timestamp_ns / 1e9
+        self.target = _text(inner.target)
+        self.module_path = _text(inner.module_path) or None
+        self.file = _text(inner.file) or None
+        self.line = int(inner.line) or None
+        self.message = _text(inner.message)
+        self.spans = [_text(inner.spans.ptr[i]) for i in range(inner.spans.len)]
+        self.fields = [(_text(inner.fields.ptr[i].key), _text(inner.fields.ptr[i].value))
+                       for i in range(inner.fields.len)]
+        lib.destroy_log_record(record)
+
+    @property
+    def logging_level(self):
+        # type: (PyLogRecord) -> int
+        """
+        Level of the standard logging module, Trace maps to 5
+        """
+        return _LOGGING_LEVELS[self.level]
+
+    def to_log_record(self):
+        # type: (PyLogRecord) -> logging.LogRecord
+        """
+        Record of the standard logging module named after the target, the fields and spans are extra attributes
+        """
+        record = logging.LogRecord(self.target.replace("::", "."), self.logging_level, self.file or "",
+                                   self.line or 0, self.message, None, None)
+        record.created = self.created
+        record.msecs = (self.created - int(self.created)) * 1000
+        if self.module_path is not None:
+            record.module = self.module_path.split("::")[-1]
+        record.spans = self.spans
+        record.fields = dict(self.fields)
+        for key, value in self.fields:
+            if not hasattr(record, key):
+                setattr(record, key, value)
+        return record
+
+    def __repr__(self):
+        return "LogRecord(level={}, target={}, message={}, spans={}, fields={})".format(
+            self.level, self.target, self.message, self.spans, self.fields)
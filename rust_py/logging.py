@@ -3,9 +3,15 @@
 
 # vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
 
+import atexit
+
 from .utils import CtypesEnum, handle_error, PyString
 from ctypes import Structure
 from utils import lib, ffi
+from .log_record import PyLogRecord
+
+# PyLogger given to @{PyLogger.configure}, its callback must outlive the installed logger
+_configured = None  # type: Optional[PyLogger]
 
 
 # @formatter:off
@@ -36,23 +42,84 @@ class PyLogTimeFormat(CtypesEnum):
 # @formatter:on
 
 
+# @formatter:off
+class PyLogOverflow(CtypesEnum):
+    DropNewest = lib.FFILogOverflowDropNewest   # noqa
+    DropOldest = lib.FFILogOverflowDropOldest   # noqa
+    Block      = lib.FFILogOverflowBlock        # noqa
+# @formatter:on
+
+
+class PyLogSink(object):
+    """
+    Output of the formatted lines besides the callback of a PyLogger, given to @{PyLogger.configure}
+    """
+
+    def __init__(self, path=None, max_bytes=0, max_age=0, retention=0):
+        # type: (PyLogSink,Optional[str],int,int,int) -> PyLogSink
+        super(PyLogSink, self).__init__()
+        self.path = path
+        self.max_bytes = max_bytes
+        self.max_age = max_age
+        self.retention = retention
+
+    @staticmethod
+    def stderr():
+        # type: () -> PyLogSink
+        return PyLogSink()
+
+    @staticmethod
+    def file(path):
+        # type: (str) -> PyLogSink
+        """
+        File opened in append mode, created if missing
+        """
+        return PyLogSink(path)
+
+    @staticmethod
+    def rotating_file(path, max_bytes=0, max_age=0, retention=5):
+        # type: (str,int,int,int) -> PyLogSink
+        """
+        File renamed to path.1 before it exceeds max_bytes or once it was written for max_age seconds, 0 disables a
+        limit. The older files shift to the next suffix and only retention of them are kept
+        """
+        return PyLogSink(path, max_bytes, max_age, retention)
+
+    def add_to(self, config):
+        # type: (PyLogSink,POINTER(FFILoggingConfig)) -> None
+        if self.path is None:
+            assert handle_error(lib.logger_config_add_stderr(config)) is None
+        else:
+            path = ffi.new('char[]', self.path.encode("utf-8"))
+            assert handle_error(
+                lib.logger_config_add_rotating_file(config, path, self.max_bytes, self.max_age, self.retention)) is None
+
+
 class PyLogger(Structure):
-    def __init__(self, callback, test_it=True):
-        # type: (PyLogger,Callable[[PyLogLevel,PyString],None]) -> PyLogger
+    def __init__(self, callback, test_it=True, structured=False):
+        # type: (PyLogger,Callable[[PyLogLevel,PyString],None],bool,bool) -> PyLogger
         """
         Create a logger from a valid callback which uses log level and a string to log it.
         String will have the error formatted as per the @{configure} method.
         Will test the callback in this constructor if test_it is True, disable it if you do not
         want it.
 
-        :param callback: Should be a function that takes an int and a PyString as argument
-        :param test_it: Boolean to indicate if the callback should be tested when the logger is built
+        :param callback: Should be a function that takes an int and a PyString as argument, or a PyLogRecord when
+                         structured is True
+        :param test_it: Boolean to indicate if the callback should be tested when the logger is built, structured
+                        loggers are always tested
+        :param structured: Boolean to receive PyLogRecord instead of formatted lines, the format given to
+                           @{configure} is then ignored
 
         :return a PyLogger implementation
 
         :raise: Exception if test_it is True and the callback is not correct
         """
         super(PyLogger, self).__init__()
+        if callback is None:
+            # Only the sinks given to configure are used
+            self.__inner = None
+            return
         if not callable(callback):
             raise Exception("Not a valid callback, should be Callable[[PyLogLevel,PyString],None]")
         self.__callback = callback
@@ -64,6 +131,19 @@ class PyLogger(Structure):
 
             return log
 
+        def record_wrapper(callback_fn):
+            @ffi.callback("void(FFILogRecord*)")
+            def log(record):
+                callback_fn(PyLogRecord(record))
+
+            return log
+
+        if structured:
+            self.__wrapper = record_wrapper(self.__callback)
+            # type: POINTER(FFILogger)
+            self.__inner = handle_error(lib.create_record_logger(self.__wrapper))
+            return
+
         self.__wrapper = wrapper(self.__callback)
 
         if test_it:
@@ -82,22 +162,209 @@ class PyLogger(Structure):
         if self.__inner is not None:
             lib.destroy_pointer(self.inner)
 
-    def configure(self, level, time_format, log_format, show_level=False, show_trace=False):
-        # type: (PyLogger,PyLogLevel,PyLogTimeFormat,PyLogFormat,Optional[bool],Optional[bool]) -> PyLogger
+    def configure(self, level, time_format, log_format, show_level=False, show_trace=False, directives=None,
+                  queue_size=0, overflow=PyLogOverflow.DropNewest, drain_thread=True, sinks=None):
+        # type: (PyLogger,PyLogLevel,PyLogTimeFormat,PyLogFormat,Optional[bool],Optional[bool],Optional[str],int,PyLogOverflow,bool,Optional[List[PyLogSink]]) -> PyLogger
         """
-        Configure the logger output
+        Configure the logger output, replacing the logger configured before. The PyLogger is kept alive until
+        @{uninstall} or the next configure, logging is uninstalled when the interpreter exits
 
         :param level: The log level
         :param time_format: The time format to use
         :param log_format: The produced log format
         :param show_level: If log level should be shown
         :param show_trace: If trace of Rust function should be shown
+        :param directives: Filter per target in the RUST_LOG syntax such as "tokio=warn,rust_py=trace", a bare level
+                           replaces level. The RUST_LOG environment variable is used when None
+        :param queue_size: When positive the threads which log only queue up to queue_size events instead of calling
+                           the callback and writing to the sinks, so a slow output does not slow down the event loop
+        :param overflow: What happens to an event logged while the queue is full, Block requires drain_thread
+        :param drain_thread: If a dedicated thread calls the callback with the queued events and writes them to the
+                             sinks, otherwise they are delivered by @{drain}
+        :param sinks: PyLogSink also receiving the formatted lines, on the thread which logged unless they are queued.
+                      A PyLogger created without callback only writes to them
 
         :return: PyLogger pass as argument for chaining
 
-        :raise: Exception if the configuration failed
+        :raise: Exception if the configuration failed or a directive is invalid
         """
-        config = lib.create_logger_config(level, time_format, log_format, show_level, show_trace)
-        assert handle_error(lib.configure_logging(config, self.inner)) is None
+        config = self.__config(level, time_format, log_format, show_level, show_trace, directives, sinks)
+        inner = ffi.NULL if self.inner is None else self.inner
+        if queue_size > 0:
+            assert handle_error(
+                lib.configure_buffered_logging(config[0], inner, queue_size, overflow, drain_thread)) is None
+        else:
+            assert handle_error(lib.configure_logging(config[0], inner)) is None
         # At this point ownership of the pointer was transferred
         self.__inner = None
+        # The logger configured before is uninstalled so its callback can go
+        global _configured
+        _configured = self
+
+    def scoped(self, level, time_format, log_format, show_level=False, show_trace=False, directives=None,
+               sinks=None):
+        # type: (PyLogger,PyLogLevel,PyLogTimeFormat,PyLogFormat,Optional[bool],Optional[bool],Optional[str],Optional[List[PyLogSink]]) -> PyLoggingScope
+        """
+        Configure the logger for the current thread only, the other threads keep the logger given to @{configure}.
+        Use the returned scope as a context manager or close it on this thread to restore the previous logger.
+        The parameters are the ones of @{configure}, @{set_level} and @{set_directives} do not change this logger
+
+        :return: PyLoggingScope to close on this thread
+
+        :raise: Exception if the configuration failed or a directive is invalid
+        """
+        config = self.__config(level, time_format, log_format, show_level, show_trace, directives, sinks)
+        inner = ffi.NULL if self.inner is None else self.inner
+        scope = handle_error(lib.configure_scoped_logging(config[0], inner))
+        # At this point ownership of the pointer was transferred
+        self.__inner = None
+        return PyLoggingScope(scope, self)
+
+    @staticmethod
+    def __config(level, time_format, log_format, show_level, show_trace, directives, sinks):
+        # type: (PyLogLevel,PyLogTimeFormat,PyLogFormat,bool,bool,Optional[str],Optional[List[PyLogSink]]) -> POINTER(FFILoggingConfig)
+        directives = ffi.NULL if directives is None else ffi.new('char[]', directives.encode("utf-8"))
+        config = ffi.new("FFILoggingConfig*",
+                         lib.create_logger_config(level, time_format, log_format, show_level, show_trace, directives))
+        for sink in sinks or []:
+            sink.add_to(config)
+        return config
+
+    @staticmethod
+    def uninstall():
+        # type: () -> None
+        """
+        Stop calling the callback of the configured logger, the queued events are delivered first. Once it returns
+        the PyLogger can be released and @{configure} can be called again
+
+        :raise: Exception if logging is not configured or if called from the callback
+        """
+        assert handle_error(lib.uninstall_logging()) is None
+        global _configured
+        _configured = None
+
+    @staticmethod
+    def set_level(level):
+        # type: (PyLogLevel) -> None
+        """
+        Change the default level of the configured logger, the per target directives are kept
+
+        :raise: Exception if logging is not configured
+        """
+        assert handle_error(lib.set_log_level(level)) is None
+
+    @staticmethod
+    def set_directives(directives):
+        # type: (str) -> None
+        """
+        Replace the per target directives of the configured logger, such as "rust_py::subscriber=debug,tokio=warn".
+        An empty string removes them
+
+        :raise: Exception if logging is not configured or a directive is invalid
+        """
+        directives = ffi.new('char[]', directives.encode("utf-8"))
+        assert handle_error(lib.set_log_directives(directives)) is None
+
+    @staticmethod
+    def log(level, target, message, fields=None):
+        # type: (PyLogLevel,str,str,Optional[Dict[str,str]]) -> None
+        """
+        Log message through the configured logger as if it was logged in Rust, inside the current span of this thread,
+        with fields mapping names to values. Filter directives match target like a Rust module path, such as
+        "app::db". Past 1024 distinct level, target and field names, events are logged with the "host" target and
+        their target and fields as fields
+
+        :raise: Exception if a field is named message or if there are more than 31 fields
+        """
+        keep = []
+
+        def text(value):
+            keep.append(ffi.new('char[]', (u"%s" % value).encode("utf-8")))
+            return keep[-1]
+
+        fields = fields or {}
+        names = [text(name) for name in fields]
+        values = [text(fields[name]) for name in fields]
+        assert handle_error(lib.log_from_host(level, text(target), text(message), ffi.new('char*[]', names),
+                                              ffi.new('char*[]', values), len(fields))) is None
+
+    @staticmethod
+    def drain(max_events=0):
+        # type: (int) -> int
+        """
+        Call the callback and write to the sinks on this thread at most max_events queued events, 0 for the ones
+        queued right now
+
+        :return: The number of events given to the callback
+
+        :raise: Exception if buffered logging is not configured
+        """
+        drained = handle_error(lib.drain_logs(max_events))
+        res = int(drained[0])
+        lib.destroy_pointer(drained)
+        return res
+
+    @staticmethod
+    def dropped():
+        # type: () -> int
+        """
+        Number of events dropped because the queue was full
+
+        :raise: Exception if buffered logging is not configured
+        """
+        dropped = handle_error(lib.dropped_logs())
+        res = int(dropped[0])
+        lib.destroy_pointer(dropped)
+        return res
+
+    @staticmethod
+    def filter():
+        # type: () -> str
+        """
+        Current filter of the configured logger in the directive syntax, such as "rust_py::subscriber=debug,info"
+
+        :raise: Exception if logging is not configured
+        """
+        return PyString(handle_error(lib.log_filter())).to_string()
+
+
+class PyLoggingScope(object):
+    def __init__(self, inner, logger):
+        # type: (PyLoggingScope,POINTER(FFILoggingScope),PyLogger) -> PyLoggingScope
+        """
+        Logger of a single thread returned by @{PyLogger.scoped}
+        """
+        self.__inner = inner
+        # The callback must outlive the scope
+        self.__logger = logger
+
+    def close(self):
+        # type: (PyLoggingScope) -> None
+        """
+        Stop calling the callback and restore the logger this thread used before, does nothing once closed
+
+        :raise: Exception if not called on the thread which created the scope or if called from the callback
+        """
+        if self.__inner is None:
+            return
+        assert handle_error(lib.exit_logging_scope(self.__inner)) is None
+        self.__inner = None
+        self.__logger = None
+
+    def __enter__(self):
+        # type: (PyLoggingScope) -> PyLoggingScope
+        return self
+
+    def __exit__(self, exc_type, exc_val, exc_tb):
+        self.close()
+
+
+@atexit.register
+def _uninstall_at_exit():
+    # type: () -> None
+    # The callbacks must not be called while the interpreter shuts down
+    try:
+        PyLogger.uninstall()
+    except Exception as e:
+        if "not configured" not in str(e):
+            raise
@@ -0,0 +1,115 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+from .event_type import PyEventType
+from .utils import lib, Structure
+
+
+class PyMetricsSnapshot(Structure):
+    """
+    Copy of the counters of a runner, its subscribers and its clients at some point in time
+    """
+
+    def __init__(self, snapshot):
+        # type: (PyMetricsSnapshot,POINTER(FFIMetricsSnapshot)) -> PyMetricsSnapshot
+        super(PyMetricsSnapshot, self).__init__()
+        inner = snapshot[0]
+        self.__events = {}
+        for i in range(inner.events.len):
+            metrics = inner.events.ptr[i]
+            self.__events[int(metrics.event_type)] = {
+                "published": int(metrics.published),
+                "delivered": int(metrics.delivered),
+                "failed": int(metrics.failed),
+                "stored": int(metrics.stored),
+                "dropped": int(metrics.dropped),
+            }
+        self.__latency = [(int(inner.latency.ptr[i].upper_bound_us), int(inner.latency.ptr[i].count))
+                          for i in range(inner.latency.len)]
+        self.__latency_count = int(inner.latency_count)
+        self.__latency_sum = inner.latency_sum_ns / 1e9
+        self.__subscribers = int(inner.subscribers)
+        self.__stored = int(inner.stored)
+        self.__channel_len = int(inner.channel_len)
+        self.__channel_peak = int(inner.channel_peak)
+        self.__lagged = int(inner.lagged)
+        self.__messages_sent = int(inner.messages_sent)
+        self.__send_errors = int(inner.send_errors)
+        # everything was copied, the native snapshot is not needed anymore
+        lib.destroy_metrics_snapshot(snapshot)
+
+    def event(self, event_type):
+        # type: (PyMetricsSnapshot,PyEventType) -> Dict[str,int]
+        """
+        Counters published, delivered, failed, stored and dropped of an event type, all 0 when it was never seen
+        """
+        return dict(self.__events.get(event_type.inner, {
+            "published": 0, "delivered": 0, "failed": 0, "stored": 0, "dropped": 0}))
+
+    @property
+    def events(self):
+        # type: (PyMetricsSnapshot) -> Dict[int,Dict[str,int]]
+        return dict(self.__events)
+
+    @property
+    def latency(self):
+        # type: (PyMetricsSnapshot) -> List[Tuple[int,int]]
+        """
+        Histogram of the callback durations as (upper bound in microseconds, count)
+        """
+        return list(self.__latency)
+
+    @property
+    def latency_count(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__latency_count
+
+    @property
+    def latency_sum(self):
+        # type: (PyMetricsSnapshot) -> float
+        """
+        Total duration of the callbacks in seconds
+        """
+        return self.__latency_sum
+
+    @property
+    def subscribers(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__subscribers
+
+    @property
+    def stored(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__stored
+
+    @property
+    def channel_len(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__channel_len
+
+    @property
+    def channel_peak(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__channel_peak
+
+    @property
+    def lagged(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__lagged
+
+    @property
+    def messages_sent(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__messages_sent
+
+    @property
+    def send_errors(self):
+        # type: (PyMetricsSnapshot) -> int
+        return self.__send_errors
+
+    def __repr__(self):
+        return "MetricsSnapshot(events={}, latency_count={}, subscribers={}, stored={}, channel_len={}, " \
+               "lagged={}, messages_sent={}, send_errors={})".format(
+                   self.__events, self.__latency_count, self.__subscribers, self.__stored, self.__channel_len,
+                   self.__lagged, self.__messages_sent, self.__send_errors)
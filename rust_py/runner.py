@@ -2,19 +2,23 @@
 # -*- coding: utf-8 -*-
 
 # vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
-from .utils import _native, Structure, handle_error
+from .utils import _native, Structure, handle_error, ffi, PyString
 from .runtime import PyRuntime
 from .subscriber import PySubscriber
 from .client_handle import PyClientHandle
+from .transport import PyTransportConfig
+from .dead_letter import PyDeadLetters
+from .metrics import PyMetricsSnapshot
 
 
 
 class PyRunner(Structure):
-    def __init__(self, runtime):
-        # type: (PyRunner,PyRuntime) -> PyRunner
+    def __init__(self, runtime, runner=None):
+        # type: (PyRunner,PyRuntime,Optional[FFIError_FFITriple]) -> PyRunner
         super(PyRunner, self).__init__()
-        # type: POINTER(FFIError_FFIRunner)
-        runner = _native.lib.create_new_runner(runtime.inner)
+        if runner is None:
+            # type: POINTER(FFIError_FFIRunner)
+            runner = _native.lib.create_new_runner(runtime.inner)
         # type: POINTER(FFITuple_FFIRunner_FFISubscriber)
         tuple = handle_error(runner)
         # type: POINTER(FFIAuditRunner)
@@ -27,6 +31,109 @@ class PyRunner(Structure):
         # type: PyRuntime
         self.__runtime = runtime
 
+    @classmethod
+    def unix(cls, runtime, path):
+        # type: (PyRunner,PyRuntime,str) -> PyRunner
+        """
+        Create a runner whose client talks to the Unix socket at path, reconnecting on failure
+
+        :raise: Exception with a string if the runner can not be instantiated
+        """
+        path = ffi.new('char[]', path.encode("utf-8"))
+        return cls(runtime, _native.lib.create_unix_client(runtime.inner, path))
+
+    @classmethod
+    def tcp(cls, runtime, addr, config=None):
+        # type: (PyRunner,PyRuntime,str,Optional[PyTransportConfig]) -> PyRunner
+        """
+        Create a runner whose client talks to the TCP peer at addr (host:port), reconnecting on failure
+
+        :raise: Exception with a string if the runner can not be instantiated
+        """
+        if config is None:
+            config = PyTransportConfig()
+        addr = ffi.new('char[]', addr.encode("utf-8"))
+        return cls(runtime, _native.lib.create_tcp_client(runtime.inner, addr, config.inner))
+
+    @classmethod
+    def websocket(cls, runtime, url, config=None):
+        # type: (PyRunner,PyRuntime,str,Optional[PyTransportConfig]) -> PyRunner
+        """
+        Create a runner whose client consumes the WebSocket feed at url (ws://host:port/path, or wss:// over
+        TLS), reconnecting on failure. The framing of the config is ignored and its keepalive is used as the ping
+        interval.
+
+        :raise: Exception with a string if the runner can not be instantiated
+        """
+        if config is None:
+            config = PyTransportConfig()
+        url = ffi.new('char[]', url.encode("utf-8"))
+        return cls(runtime, _native.lib.create_websocket_client(runtime.inner, url, config.inner))
+
+    @classmethod
+    def process(cls, runtime, command, args=()):
+        # type: (PyRunner,PyRuntime,str,Sequence[str]) -> PyRunner
+        """
+        Create a runner whose client runs command as a child process on the Rust side, its stdout and stderr
+        lines and its exit code are delivered as events and messages sent through the client are written to its
+        stdin
+
+        :raise: Exception with a string if the runner can not be instantiated
+        """
+        command = ffi.new('char[]', command.encode("utf-8"))
+        # keep the argument buffers alive for the duration of the call
+        buffers = [ffi.new('char[]', arg.encode("utf-8")) for arg in args]
+        argv = ffi.new('char*[]', buffers)
+        return cls(runtime, _native.lib.create_process_client(runtime.inner, command, argv, len(buffers)))
+
+    def add_unix_client(self, path):
+        # type: (PyRunner,str) -> PyClientHandle
+        """
+        Merge the events of another client talking to the Unix socket at path, see PyClientHandle.source
+
+        :raise: Exception with a string if the client can not be instantiated
+        """
+        path = ffi.new('char[]', path.encode("utf-8"))
+        return PyClientHandle(handle_error(_native.lib.runner_add_unix_client(self.inner, path)))
+
+    def add_tcp_client(self, addr, config=None):
+        # type: (PyRunner,str,Optional[PyTransportConfig]) -> PyClientHandle
+        """
+        Merge the events of another client talking to the TCP peer at addr (host:port)
+
+        :raise: Exception with a string if the client can not be instantiated
+        """
+        if config is None:
+            config = PyTransportConfig()
+        addr = ffi.new('char[]', addr.encode("utf-8"))
+        return PyClientHandle(handle_error(_native.lib.runner_add_tcp_client(self.inner, addr, config.inner)))
+
+    def add_websocket_client(self, url, config=None):
+        # type: (PyRunner,str,Optional[PyTransportConfig]) -> PyClientHandle
+        """
+        Merge the events of another client consuming the WebSocket feed at url (ws://host:port/path, or wss:// over TLS)
+
+        :raise: Exception with a string if the client can not be instantiated
+        """
+        if config is None:
+            config = PyTransportConfig()
+        url = ffi.new('char[]', url.encode("utf-8"))
+        return PyClientHandle(handle_error(_native.lib.runner_add_websocket_client(self.inner, url, config.inner)))
+
+    def add_process_client(self, command, args=()):
+        # type: (PyRunner,str,Sequence[str]) -> PyClientHandle
+        """
+        Merge the events of another client running command as a child process
+
+        :raise: Exception with a string if the client can not be instantiated
+        """
+        command = ffi.new('char[]', command.encode("utf-8"))
+        # keep the argument buffers alive for the duration of the call
+        buffers = [ffi.new('char[]', arg.encode("utf-8")) for arg in args]
+        argv = ffi.new('char*[]', buffers)
+        return PyClientHandle(handle_error(_native.lib.runner_add_process_client(self.inner, command, argv,
+                                                                                  len(buffers))))
+
     @property
     def inner(self):
         # type: (PyRunner) -> POINTER(FFIRunner)
@@ -42,5 +149,152 @@ class PyRunner(Structure):
         # type: (PyRunner) -> PyClient
         return self.__client
 
+    def add_timeout(self, event_type, payload, delay_ms):
+        # type: (PyRunner,PyEventType,str,int) -> int
+        """
+        Emit an event of event_type carrying payload once after delay_ms
+
+        :return: The timer id to be used with cancel_timer, also the source id of its events
+        """
+        payload = ffi.new('char[]', payload.encode("utf-8"))
+        return self.__id(_native.lib.runner_add_timeout(self.inner, event_type.inner, payload, delay_ms))
+
+    def add_interval(self, event_type, payload, interval_ms):
+        # type: (PyRunner,PyEventType,str,int) -> int
+        """
+        Emit an event of event_type carrying payload every interval_ms
+
+        :return: The timer id to be used with cancel_timer, also the source id of its events
+        """
+        payload = ffi.new('char[]', payload.encode("utf-8"))
+        return self.__id(_native.lib.runner_add_interval(self.inner, event_type.inner, payload, interval_ms))
+
+    def add_cron(self, event_type, payload, expression):
+        # type: (PyRunner,PyEventType,str,str) -> int
+        """
+        Emit an event of event_type carrying payload following a cron expression in UTC
+        (sec min hour day month weekday [year])
+
+        :return: The timer id to be used with cancel_timer, also the source id of its events
+        """
+        payload = ffi.new('char[]', payload.encode("utf-8"))
+        expression = ffi.new('char[]', expression.encode("utf-8"))
+        return self.__id(_native.lib.runner_add_cron(self.inner, event_type.inner, payload, expression))
+
+    def cancel_timer(self, timer_id):
+        # type: (PyRunner,int) -> None
+        """
+        :raise: Exception if the timer is unknown or already finished
+        """
+        assert handle_error(_native.lib.runner_cancel_timer(self.inner, timer_id)) is None
+
+    def watch_path(self, path, recursive=False, debounce_ms=100, max_wait_ms=1000):
+        # type: (PyRunner,str,bool,int,int) -> int
+        """
+        Emit file created/modified/removed events for path, changes are merged until nothing
+        happened for debounce_ms but for at most max_wait_ms after the first of them, so a path
+        changing continuously still emits events
+
+        :return: The watch id to be used with unwatch_path, also the source id of its events
+        """
+        path = ffi.new('char[]', path.encode("utf-8"))
+        return self.__id(_native.lib.runner_watch_path(self.inner, path, recursive, debounce_ms,
+                                                                 max_wait_ms))
+
+    def unwatch_path(self, watch_id):
+        # type: (PyRunner,int) -> None
+        """
+        :raise: Exception if the watch is unknown
+        """
+        assert handle_error(_native.lib.runner_unwatch_path(self.inner, watch_id)) is None
+
+    def enable_signals(self, kill_on_terminate=False):
+        # type: (PyRunner,bool) -> int
+        """
+        Emit signal events on SIGINT/SIGTERM/SIGHUP/SIGUSR1 instead of the python handlers,
+        SIGTERM is followed by a kill event if kill_on_terminate is set
+
+        :return: The source id of the signal events
+        """
+        return self.__id(_native.lib.runner_enable_signals(self.inner, kill_on_terminate))
+
+    def disable_signals(self):
+        # type: (PyRunner) -> None
+        """
+        :raise: Exception if signals were not enabled
+        """
+        assert handle_error(_native.lib.runner_disable_signals(self.inner)) is None
+
+    def metrics(self):
+        # type: (PyRunner) -> PyMetricsSnapshot
+        """
+        Copy of the counters of the runner, its subscribers and its clients
+        """
+        return PyMetricsSnapshot(handle_error(_native.lib.runner_metrics_snapshot(self.inner)))
+
+    def prometheus(self):
+        # type: (PyRunner) -> str
+        """
+        Metrics in the Prometheus text exposition format
+
+        :raise: Exception if the library was built without the prometheus feature
+        """
+        if not hasattr(_native.lib, "runner_metrics_prometheus"):
+            raise Exception("The library was built without the prometheus feature")
+        return PyString(handle_error(_native.lib.runner_metrics_prometheus(self.inner))).to_string()
+
+    @property
+    def dead_letter_count(self):
+        # type: (PyRunner) -> int
+        """
+        Number of events the subscribers failed to handle
+        """
+        count = handle_error(_native.lib.runner_dead_letters_len(self.inner))
+        res = int(count[0])
+        _native.lib.destroy_pointer(count)
+        return res
+
+    def dead_letters(self):
+        # type: (PyRunner) -> PyDeadLetters
+        """
+        Copy of the events the subscribers failed to handle, oldest first
+        """
+        return PyDeadLetters(handle_error(_native.lib.runner_dead_letters_inspect(self.inner)))
+
+    def drain_dead_letters(self, max_count):
+        # type: (PyRunner,int) -> PyDeadLetters
+        """
+        Remove up to max_count dead letters, oldest first
+        """
+        return PyDeadLetters(handle_error(_native.lib.runner_dead_letters_drain(self.inner, max_count)))
+
+    def set_retry_policy(self, max_attempts, backoff_ms, multiplier=2.0):
+        # type: (PyRunner,int,int,float) -> None
+        """
+        Deliver a failing event up to max_attempts times before it becomes a dead letter, waiting backoff_ms
+        before the first retry and multiplying the delay by multiplier after each one
+
+        :raise: Exception if max_attempts is 0
+        """
+        assert handle_error(_native.lib.runner_set_retry_policy(self.inner, max_attempts, backoff_ms,
+                                                                multiplier)) is None
+
+    def set_breaker_policy(self, failure_threshold, cooldown_ms):
+        # type: (PyRunner,int,int) -> None
+        """
+        Suspend a subscriber for cooldown_ms once it failed failure_threshold times in a row, 0 never suspends it,
+        and remove it for good when cooldown_ms is 0. Applies to the subscribers which subscribe afterwards, by
+        default a subscriber is suspended for 30s after 5 failures
+        """
+        assert handle_error(_native.lib.runner_set_breaker_policy(self.inner, failure_threshold,
+                                                                  cooldown_ms)) is None
+
+    @staticmethod
+    def __id(result):
+        timer_id = handle_error(result)
+        res = int(timer_id[0])
+        _native.lib.destroy_pointer(timer_id)
+        return res
+
     def __del__(self):
         _native.lib.destroy_pointer(self.inner)
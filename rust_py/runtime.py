@@ -3,7 +3,7 @@
 
 # vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
 
-from .utils import Structure, handle_error, lib
+from .utils import Structure, handle_error, lib, ffi
 
 
 class PyRuntime(Structure):
@@ -25,5 +25,32 @@ class PyRuntime(Structure):
         # type: (PyRuntime) -> POINTER(FFIRuntime)
         return self.__inner
 
+    def serve_metrics(self, runner, addr="127.0.0.1:0"):
+        # type: (PyRuntime,PyRunner,str) -> int
+        """
+        Serve the metrics of runner in the Prometheus text exposition format on GET /metrics at addr
+
+        :return: The bound port, useful when addr uses port 0
+
+        :raise: Exception if the address can not be bound, metrics are already served or the library was built
+                without the prometheus feature
+        """
+        if not hasattr(lib, "runtime_serve_metrics"):
+            raise Exception("The library was built without the prometheus feature")
+        addr = ffi.new('char[]', addr.encode("utf-8"))
+        port = handle_error(lib.runtime_serve_metrics(self.inner, runner.inner, addr))
+        res = int(port[0])
+        lib.destroy_pointer(port)
+        return res
+
+    def stop_metrics(self):
+        # type: (PyRuntime) -> None
+        """
+        Stop serving the metrics started by serve_metrics
+
+        :raise: Exception if metrics are not served
+        """
+        assert handle_error(lib.runtime_stop_metrics(self.inner)) is None
+
     def __del__(self):
         lib.destroy_pointer(self.inner)
@@ -0,0 +1,15 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+
+from .utils import CtypesEnum, lib
+
+
+# @formatter:off
+class PySignal(CtypesEnum):
+    Interrupt = lib.FFISignalInterrupt  # noqa
+    Terminate = lib.FFISignalTerminate  # noqa
+    Hangup    = lib.FFISignalHangup     # noqa
+    User1     = lib.FFISignalUser1      # noqa
+# @formatter:on
@@ -24,12 +24,55 @@ class PySubscriber(Structure):
 
     def subscribe(self, event_type, callback, runtime):
         # type: (PySubscriber, PyEventType, Callable[[PyEvent],None], PyRuntime) -> None
+        wrapper = self.__wrap(callback)
+        # this returns a FFINull, no need to check it
+        assert handle_error(lib.subscribe(self.inner, event_type.inner, wrapper, runtime.inner)) is None
+
+    def subscribe_filter(self, spec, callback, runtime):
+        # type: (PySubscriber, str, Callable[[PyEvent],None], PyRuntime) -> None
+        """
+        Subscribe to the events accepted by a filter spec made of ';' separated clauses:
+        type=A,B (type names or numbers), prefix=text on the payload, priority=n where higher priorities are called
+        first and regex=expr on the payload, which takes the rest of the spec. An empty spec or * receives every event,
+        e.g. "type=EventType1;prefix=test"
+
+        :raise: Exception with a string if the spec is not valid
+        """
+        wrapper = self.__wrap(callback)
+        spec = ffi.new('char[]', spec.encode("utf-8"))
+        assert handle_error(lib.subscribe_filter(self.inner, spec, wrapper, runtime.inner)) is None
+
+    def intercept(self, spec, callback, runtime):
+        # type: (PySubscriber, str, Callable[[PyEvent],bool], PyRuntime) -> None
+        """
+        Run the callback on the events accepted by the filter spec (see subscribe_filter) before any subscriber.
+        It can change the payload with PyEvent.set_payload and returns False to drop the event. The event must not
+        be kept once the callback returned
+
+        :raise: Exception with a string if the spec is not valid
+        """
+        if not callable(callback):
+            raise Exception("Not a valid callback, should be Callable[[PyEvent],bool]")
+
+        def wrapper(callback_fn):
+            @ffi.callback("bool(FFISEnvelope *)")
+            def intercept_event(event):
+                return bool(callback_fn(PyEvent(event)))
+
+            return intercept_event
+
+        wrapper = wrapper(callback)
+        self.__callbacks.append(callback)
+        self.__wrappers.append(wrapper)
+        spec = ffi.new('char[]', spec.encode("utf-8"))
+        assert handle_error(lib.subscribe_interceptor(self.inner, spec, wrapper, runtime.inner)) is None
 
+    def __wrap(self, callback):
         if not callable(callback):
             raise Exception("Not a valid callback, should be Callable[[PyEvent],None]")
 
         def wrapper(callback_fn):
-            @ffi.callback("void(FFISEvent *)")
+            @ffi.callback("void(FFISEnvelope *)")
             def receive_event(event):
                 callback_fn(PyEvent(event))
 
@@ -38,5 +81,4 @@ class PySubscriber(Structure):
         wrapper = wrapper(callback)
         self.__callbacks.append(callback)
         self.__wrappers.append(wrapper)
-        # this returns a FFINull, no need to check it
-        assert handle_error(lib.subscribe(self.inner, event_type.inner, wrapper, runtime.inner)) is None
+        return wrapper
@@ -0,0 +1,81 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+from .utils import ffi, lib, Structure, handle_error
+
+
+class PyTopicEvent(Structure):
+    def __init__(self, event):
+        # type: (PyTopicEvent, POINTER(FFITopicEvent)) -> PyTopicEvent
+        super(PyTopicEvent, self).__init__()
+        self.__inner = event
+
+    @property
+    def topic(self):
+        # type: (PyTopicEvent) -> str
+        return ffi.string(self.__inner.topic.ptr, self.__inner.topic.len)
+
+    @property
+    def payload(self):
+        # type: (PyTopicEvent) -> str
+        return ffi.string(self.__inner.payload.ptr, self.__inner.payload.len)
+
+    def __repr__(self):
+        return self.topic + " " + self.payload
+
+
+class PyTopicServer(Structure):
+    def __init__(self, runtime):
+        # type: (PyTopicServer, PyRuntime) -> PyTopicServer
+        """
+        Topic mode event loop, events are published on dot separated topics such as orders.eu.created and
+        subscribers use * for one segment and a trailing > for all the remaining ones (orders.*.created, orders.>)
+
+        :raise: Exception with a string if the server can not be instantiated
+        """
+        super(PyTopicServer, self).__init__()
+        self.__inner = handle_error(lib.create_topic_server(runtime.inner))
+        self.__callbacks = []
+        self.__wrappers = []
+        # we store the runtime to avoid a strong reference delete before the server has been shut down
+        # type: PyRuntime
+        self.__runtime = runtime
+
+    @property
+    def inner(self):
+        # type: (PyTopicServer) -> POINTER(FFITopicServer)
+        return self.__inner
+
+    def __del__(self):
+        assert handle_error(lib.destroy_topic_server(self.inner)) is None
+
+    def publish(self, topic, payload):
+        # type: (PyTopicServer, str, str) -> None
+        """
+        :raise: Exception with a string if the topic has an empty segment or a wildcard
+        """
+        topic = ffi.new('char[]', topic.encode("utf-8"))
+        payload = ffi.new('char[]', payload.encode("utf-8"))
+        assert handle_error(lib.topic_publish(self.inner, topic, payload)) is None
+
+    def subscribe(self, pattern, callback):
+        # type: (PyTopicServer, str, Callable[[PyTopicEvent],None]) -> None
+        """
+        :raise: Exception with a string if the pattern is not valid
+        """
+        if not callable(callback):
+            raise Exception("Not a valid callback, should be Callable[[PyTopicEvent],None]")
+
+        def wrapper(callback_fn):
+            @ffi.callback("void(FFITopicEvent *)")
+            def receive_event(event):
+                callback_fn(PyTopicEvent(event))
+
+            return receive_event
+
+        wrapper = wrapper(callback)
+        self.__callbacks.append(callback)
+        self.__wrappers.append(wrapper)
+        pattern = ffi.new('char[]', pattern.encode("utf-8"))
+        assert handle_error(lib.topic_subscribe(self.inner, pattern, wrapper)) is None
@@ -0,0 +1,40 @@
+#!/usr/bin/env python2.7
+# -*- coding: utf-8 -*-
+
+# vim: fileencoding=utf-8 filetype=python autoindent expandtab shiftwidth=4 softtabstop=4 tabstop=4
+
+from ctypes import Structure
+from .utils import CtypesEnum, lib
+
+
+# @formatter:off
+class PyFraming(CtypesEnum):
+    LengthDelimited = lib.FFIFramingLengthDelimited     # noqa
+    Newline         = lib.FFIFramingNewline             # noqa
+# @formatter:on
+
+
+class PyTransportConfig(Structure):
+    def __init__(self, framing=PyFraming.LengthDelimited, connect_timeout_ms=10000, read_timeout_ms=0,
+                 write_timeout_ms=10000, keepalive_ms=60000, backoff_initial_ms=100, backoff_max_ms=30000):
+        # type: (PyTransportConfig,PyFraming,int,int,int,int,int,int) -> PyTransportConfig
+        """
+        Configure a stream client, a timeout of 0 means waiting forever and a keepalive of 0 disables it
+
+        :param framing: How messages are delimited on the stream
+        :param connect_timeout_ms: Connection attempt timeout
+        :param read_timeout_ms: Reconnect if nothing was received for that long, even from a healthy but idle peer
+        :param write_timeout_ms: Reconnect if a message could not be written in that delay
+        :param keepalive_ms: TCP keepalive idle time
+        :param backoff_initial_ms: First delay between two connection attempts, doubled on each failure
+        :param backoff_max_ms: Maximum delay between two connection attempts, never shorter than backoff_initial_ms
+        """
+        super(PyTransportConfig, self).__init__()
+        # type: FFITransportConfig
+        self.__inner = lib.create_transport_config(framing, connect_timeout_ms, read_timeout_ms, write_timeout_ms,
+                                                   keepalive_ms, backoff_initial_ms, backoff_max_ms)
+
+    @property
+    def inner(self):
+        # type: (PyTransportConfig) -> FFITransportConfig
+        return self.__inner
@@ -1,3 +1,5 @@
+import os
+
 from setuptools import setup, find_packages
 
 NAME = 'rust_py'
@@ -5,8 +7,10 @@ NAME = 'rust_py'
 
 def build_native(spec):
     # Step 1: build the rust library
+    # comma separated cargo features such as prometheus
+    features = os.environ.get('RUST_PY_FEATURES', '')
     build = spec.add_external_build(
-        cmd=['cargo', 'build', '--release'],
+        cmd=['cargo', 'build', '--release'] + (['--features', features] if features else []),
         path='.'
     )
 
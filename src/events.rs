@@ -1,15 +1,25 @@
-use crate::subscriber::{
-    Subscriber,
-    SubscriberCallback,
-    SubscriberConfig,
-    SubscriberError,
-    SubscriberEvent,
-    SubscriberEventType,
+use crate::{
+    subscriber::{
+        Subscriber,
+        SubscriberCallback,
+        SubscriberConfig,
+        SubscriberError,
+        SubscriberEvent,
+        SubscriberEventType,
+    },
+    source::{FileChange, Signal, SourceId},
+    subscriber::{BreakerPolicy, Suspension},
+    trace::TraceContext,
+    transport::ClientEvent,
 };
 use anyhow::anyhow;
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display, Formatter},
     panic::{catch_unwind, RefUnwindSafe, UnwindSafe},
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
 };
 
 #[derive(Clone, Debug)]
@@ -17,10 +27,50 @@ pub enum SEvent {
     Event1(String),
     Event2(String),
     Kill,
+    Connected(String),
+    Disconnected(String),
+    Reconnecting(String),
+    ProcessStdout(String),
+    ProcessStderr(String),
+    ProcessExited(i32),
+    FileCreated(String),
+    FileModified(String),
+    FileRemoved(String),
+    Signal(Signal),
+    /// A subscriber stopped being called, see [`Suspension`] for the payload
+    SubscriberSuspended(String),
 }
 
 impl UnwindSafe for SEvent {}
 
+impl SEvent {
+    /// Build an event of the given type carrying `payload`, the payload is
+    /// ignored for [`SEventType::EventTypeKill`], has to be an integer for
+    /// [`SEventType::EventTypeProcessExited`] and a signal name such as
+    /// `SIGTERM` for [`SEventType::EventTypeSignal`]
+    ///
+    /// # Errors
+    /// If the payload does not fit the event type
+    pub fn new(event_type: SEventType, payload: String) -> anyhow::Result<Self> {
+        Ok(match event_type {
+            SEventType::EventType1 => SEvent::Event1(payload),
+            SEventType::EventType2 => SEvent::Event2(payload),
+            SEventType::EventTypeKill => SEvent::Kill,
+            SEventType::EventTypeConnected => SEvent::Connected(payload),
+            SEventType::EventTypeDisconnected => SEvent::Disconnected(payload),
+            SEventType::EventTypeReconnecting => SEvent::Reconnecting(payload),
+            SEventType::EventTypeProcessStdout => SEvent::ProcessStdout(payload),
+            SEventType::EventTypeProcessStderr => SEvent::ProcessStderr(payload),
+            SEventType::EventTypeProcessExited => SEvent::ProcessExited(payload.parse()?),
+            SEventType::EventTypeFileCreated => SEvent::FileCreated(payload),
+            SEventType::EventTypeFileModified => SEvent::FileModified(payload),
+            SEventType::EventTypeFileRemoved => SEvent::FileRemoved(payload),
+            SEventType::EventTypeSignal => SEvent::Signal(payload.parse()?),
+            SEventType::EventTypeSubscriberSuspended => SEvent::SubscriberSuspended(payload),
+        })
+    }
+}
+
 impl<F: Sized + Sync, SEvent> SubscriberCallback<SEvent> for F
     where
         Self: Fn(SEvent) + Send + UnwindSafe + RefUnwindSafe,
@@ -39,10 +89,110 @@ impl SubscriberEvent for SEvent {
             SEvent::Event1(_) => SEventType::EventType1,
             SEvent::Event2(_) => SEventType::EventType2,
             SEvent::Kill => SEventType::EventTypeKill,
+            SEvent::Connected(_) => SEventType::EventTypeConnected,
+            SEvent::Disconnected(_) => SEventType::EventTypeDisconnected,
+            SEvent::Reconnecting(_) => SEventType::EventTypeReconnecting,
+            SEvent::ProcessStdout(_) => SEventType::EventTypeProcessStdout,
+            SEvent::ProcessStderr(_) => SEventType::EventTypeProcessStderr,
+            SEvent::ProcessExited(_) => SEventType::EventTypeProcessExited,
+            SEvent::FileCreated(_) => SEventType::EventTypeFileCreated,
+            SEvent::FileModified(_) => SEventType::EventTypeFileModified,
+            SEvent::FileRemoved(_) => SEventType::EventTypeFileRemoved,
+            SEvent::Signal(_) => SEventType::EventTypeSignal,
+            SEvent::SubscriberSuspended(_) => SEventType::EventTypeSubscriberSuspended,
+        }
+    }
+
+    fn payload(&self) -> Option<&str> {
+        match self {
+            SEvent::Event1(s)
+            | SEvent::Event2(s)
+            | SEvent::Connected(s)
+            | SEvent::Disconnected(s)
+            | SEvent::Reconnecting(s)
+            | SEvent::ProcessStdout(s)
+            | SEvent::ProcessStderr(s)
+            | SEvent::FileCreated(s)
+            | SEvent::FileModified(s)
+            | SEvent::FileRemoved(s)
+            | SEvent::SubscriberSuspended(s) => Some(s),
+            SEvent::Kill | SEvent::ProcessExited(_) | SEvent::Signal(_) => None,
+        }
+    }
+}
+
+/// Event delivered to the subscribers along with its metadata
+#[derive(Clone, Debug)]
+pub struct SEnvelope {
+    /// Unique id of the event within its runner, 0 for the events raised by
+    /// the subscriber server itself
+    pub id:        u64,
+    /// Position of the event among the events of its type, starting at 1, 0
+    /// for the events raised by the subscriber server itself
+    pub sequence:  u64,
+    /// When the event entered the runner
+    pub timestamp: SystemTime,
+    /// Source which produced the event
+    pub source:    SourceId,
+    pub event:     SEvent,
+    /// Trace started by [`crate::ClientHandle::send_msg`] for the events
+    /// made of a sent message
+    pub trace:     Option<TraceContext>,
+}
+
+/// Wraps events in their [`SEnvelope`] as they enter the runner
+#[derive(Debug, Default)]
+pub struct SSequencer {
+    next_id:   u64,
+    sequences: HashMap<SEventType, u64>,
+}
+
+impl SSequencer {
+    pub fn envelope(&mut self, source: SourceId, event: SEvent) -> SEnvelope {
+        self.next_id += 1;
+        let sequence = self.sequences.entry(event.get_type()).or_insert(0);
+        *sequence += 1;
+        SEnvelope {
+            id: self.next_id,
+            sequence: *sequence,
+            timestamp: SystemTime::now(),
+            source,
+            event,
+            trace: None,
         }
     }
 }
 
+impl SubscriberEvent for SEnvelope {
+    type Type = SEventType;
+
+    fn should_kill(&self) -> bool {
+        self.event.should_kill()
+    }
+
+    fn get_type(&self) -> Self::Type {
+        self.event.get_type()
+    }
+
+    fn payload(&self) -> Option<&str> {
+        self.event.payload()
+    }
+
+    fn trace(&self) -> Option<&TraceContext> {
+        self.trace.as_ref()
+    }
+
+    fn suspended(suspension: &Suspension) -> Option<Self> {
+        Some(SEnvelope {
+            id: 0,
+            sequence: 0,
+            timestamp: SystemTime::now(),
+            source: crate::SUBSCRIBER_SERVER_SOURCE,
+            event: SEvent::SubscriberSuspended(suspension.to_string()),
+            trace: None,
+        })
+    }
+}
 
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 #[repr(C)]
@@ -53,6 +203,28 @@ pub enum SEventType {
     EventType2,
     /// Kill map to 0x2
     EventTypeKill,
+    /// Connected maps to 0x3
+    EventTypeConnected,
+    /// Disconnected maps to 0x4
+    EventTypeDisconnected,
+    /// Reconnecting maps to 0x5
+    EventTypeReconnecting,
+    /// Process stdout maps to 0x6
+    EventTypeProcessStdout,
+    /// Process stderr maps to 0x7
+    EventTypeProcessStderr,
+    /// Process exited maps to 0x8
+    EventTypeProcessExited,
+    /// File created maps to 0x9
+    EventTypeFileCreated,
+    /// File modified maps to 0xA
+    EventTypeFileModified,
+    /// File removed maps to 0xB
+    EventTypeFileRemoved,
+    /// Signal maps to 0xC
+    EventTypeSignal,
+    /// Subscriber suspended maps to 0xD
+    EventTypeSubscriberSuspended,
 }
 
 impl Display for SEventType {
@@ -63,16 +235,50 @@ impl Display for SEventType {
 
 impl SubscriberEventType for SEventType {}
 
+/// Parse either the variant name such as `EventTypeConnected` or its numeric
+/// value such as `3` or `0x3`
+impl FromStr for SEventType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = match s.strip_prefix("0x") {
+            Some(hex) => u16::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        };
+        if let Some(number) = number {
+            return SEventType::try_from(number);
+        }
+        (0..=SEventType::EVENT_SUBSCRIBER_SUSPENDED)
+            .filter_map(|n| SEventType::try_from(n).ok())
+            .find(|event_type| format!("{:?}", event_type) == s)
+            .ok_or_else(|| anyhow!("Unknown event type {}", s))
+    }
+}
+
 impl SEventType {
     pub const EVENT_1: u16 = 0x0;
     pub const EVENT_2: u16 = 0x1;
     pub const EVENT_KILL: u16 = 0x2;
+    pub const EVENT_CONNECTED: u16 = 0x3;
+    pub const EVENT_DISCONNECTED: u16 = 0x4;
+    pub const EVENT_RECONNECTING: u16 = 0x5;
+    pub const EVENT_PROCESS_STDOUT: u16 = 0x6;
+    pub const EVENT_PROCESS_STDERR: u16 = 0x7;
+    pub const EVENT_PROCESS_EXITED: u16 = 0x8;
+    pub const EVENT_FILE_CREATED: u16 = 0x9;
+    pub const EVENT_FILE_MODIFIED: u16 = 0xA;
+    pub const EVENT_FILE_REMOVED: u16 = 0xB;
+    pub const EVENT_SIGNAL: u16 = 0xC;
+    pub const EVENT_SUBSCRIBER_SUSPENDED: u16 = 0xD;
 }
 
 #[derive(Debug)]
 pub struct SConfig {
     channel_size: u32,
     sub_count: u32,
+    /// Shared with the [`crate::Runner`] so it can be changed once the
+    /// server runs
+    pub(crate) breaker_policy: Arc<Mutex<BreakerPolicy>>,
 }
 
 impl SubscriberConfig for SConfig {
@@ -83,6 +289,10 @@ impl SubscriberConfig for SConfig {
     fn channel_size(&self) -> usize {
         self.channel_size as usize
     }
+
+    fn breaker_policy(&self) -> BreakerPolicy {
+        *self.breaker_policy.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl Default for SConfig {
@@ -90,6 +300,7 @@ impl Default for SConfig {
         Self {
             channel_size: 1024,
             sub_count: 1,
+            breaker_policy: Arc::new(Mutex::new(BreakerPolicy::default())),
         }
     }
 }
@@ -102,6 +313,17 @@ impl TryFrom<u16> for SEventType {
             SEventType::EVENT_1 => Self::EventType1,
             SEventType::EVENT_2 => Self::EventType2,
             SEventType::EVENT_KILL => Self::EventTypeKill,
+            SEventType::EVENT_CONNECTED => Self::EventTypeConnected,
+            SEventType::EVENT_DISCONNECTED => Self::EventTypeDisconnected,
+            SEventType::EVENT_RECONNECTING => Self::EventTypeReconnecting,
+            SEventType::EVENT_PROCESS_STDOUT => Self::EventTypeProcessStdout,
+            SEventType::EVENT_PROCESS_STDERR => Self::EventTypeProcessStderr,
+            SEventType::EVENT_PROCESS_EXITED => Self::EventTypeProcessExited,
+            SEventType::EVENT_FILE_CREATED => Self::EventTypeFileCreated,
+            SEventType::EVENT_FILE_MODIFIED => Self::EventTypeFileModified,
+            SEventType::EVENT_FILE_REMOVED => Self::EventTypeFileRemoved,
+            SEventType::EVENT_SIGNAL => Self::EventTypeSignal,
+            SEventType::EVENT_SUBSCRIBER_SUSPENDED => Self::EventTypeSubscriberSuspended,
             _ => {
                 return Err(anyhow!("Not a valid Event type"));
             }
@@ -115,33 +337,84 @@ impl From<SEventType> for u16 {
             SEventType::EventType1 => SEventType::EVENT_1,
             SEventType::EventType2 => SEventType::EVENT_2,
             SEventType::EventTypeKill => SEventType::EVENT_KILL,
+            SEventType::EventTypeConnected => SEventType::EVENT_CONNECTED,
+            SEventType::EventTypeDisconnected => SEventType::EVENT_DISCONNECTED,
+            SEventType::EventTypeReconnecting => SEventType::EVENT_RECONNECTING,
+            SEventType::EventTypeProcessStdout => SEventType::EVENT_PROCESS_STDOUT,
+            SEventType::EventTypeProcessStderr => SEventType::EVENT_PROCESS_STDERR,
+            SEventType::EventTypeProcessExited => SEventType::EVENT_PROCESS_EXITED,
+            SEventType::EventTypeFileCreated => SEventType::EVENT_FILE_CREATED,
+            SEventType::EventTypeFileModified => SEventType::EVENT_FILE_MODIFIED,
+            SEventType::EventTypeFileRemoved => SEventType::EVENT_FILE_REMOVED,
+            SEventType::EventTypeSignal => SEventType::EVENT_SIGNAL,
+            SEventType::EventTypeSubscriberSuspended => SEventType::EVENT_SUBSCRIBER_SUSPENDED,
         }
     }
 }
 
+impl From<ClientEvent> for SEvent {
+    fn from(event: ClientEvent) -> Self {
+        match event {
+            ClientEvent::Connected(peer) => SEvent::Connected(peer),
+            ClientEvent::Disconnected(peer) => SEvent::Disconnected(peer),
+            ClientEvent::Reconnecting(peer) => SEvent::Reconnecting(peer),
+            ClientEvent::Stdout(line) => SEvent::ProcessStdout(line),
+            ClientEvent::Stderr(line) => SEvent::ProcessStderr(line),
+            ClientEvent::Exited(code) => SEvent::ProcessExited(code),
+        }
+    }
+}
+
+impl From<(FileChange, String)> for SEvent {
+    fn from((change, path): (FileChange, String)) -> Self {
+        match change {
+            FileChange::Created => SEvent::FileCreated(path),
+            FileChange::Modified => SEvent::FileModified(path),
+            FileChange::Removed => SEvent::FileRemoved(path),
+        }
+    }
+}
+
+impl From<Signal> for SEvent {
+    fn from(signal: Signal) -> Self {
+        SEvent::Signal(signal)
+    }
+}
+
 pub struct Sub<Event> {
     callback: Box<dyn SubscriberCallback<Event>>,
 }
 
-impl Sub<SEvent> {
-    pub fn new<T: SubscriberCallback<SEvent> + 'static>(callback: T) -> Self {
+impl<Event: SubscriberEvent> Sub<Event> {
+    pub fn new<T: SubscriberCallback<Event> + 'static>(callback: T) -> Self {
         Self {
             callback: Box::new(callback),
         }
     }
 }
 
-impl Subscriber<SEvent, SError> for Sub<SEvent> {
-    fn new<T: SubscriberCallback<SEvent> + 'static>(callback: T) -> Self {
+impl<Event> Subscriber<Event, SError> for Sub<Event>
+    where
+        Event: SubscriberEvent + UnwindSafe,
+{
+    fn new<T: SubscriberCallback<Event> + 'static>(callback: T) -> Self {
         Self {
             callback: Box::new(callback),
         }
     }
 
-    fn notify(&self, event: SEvent) -> Result<(), SError> {
+    fn notify(&self, event: Event) -> Result<(), SError> {
         match catch_unwind(move || (self.callback)(event)) {
             Ok(..) => Ok(()),
-            Err(e) => Err(anyhow!(format!("Error while calling callback: {:?}", e)).into()),
+            Err(e) => {
+                // panic payloads are the message most of the time
+                let reason = e
+                    .downcast_ref::<&str>()
+                    .map(|reason| (*reason).to_string())
+                    .or_else(|| e.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| format!("{:?}", e));
+                Err(anyhow!("Error while calling callback: {}", reason).into())
+            },
         }
     }
 }
@@ -17,6 +17,16 @@ impl<T> From<Vec<T>> for FFIArray<T> {
     }
 }
 
+impl<T> FFIArray<T> {
+    /// Take back the vector this array was created from
+    ///
+    /// # Safety
+    /// The array must come from [`FFIArray::from`] and not be used afterwards
+    pub(crate) unsafe fn into_vec(self) -> Vec<T> {
+        Vec::from_raw_parts(self.ptr, self.len, self.capacity)
+    }
+}
+
 impl<T: Clone> From<&[T]> for FFIArray<T> {
     fn from(vec: &[T]) -> Self {
         Self::from(Vec::from(vec))
@@ -26,6 +26,7 @@ impl FFIClient {
         FFIError::from_value(Self {
             handle: FFIClientHandle {
                 handle,
+                source: None,
             },
             receiver,
             runtime_handle: rt.handle(),
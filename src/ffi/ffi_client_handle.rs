@@ -1,4 +1,4 @@
-use crate::{ClientHandle};
+use crate::{source::SourceId, ClientHandle};
 use anyhow::anyhow;
 
 use crate::ffi::{
@@ -11,6 +11,8 @@ use crate::ffi::{
 
 pub struct FFIClientHandle {
     pub handle:         ClientHandle,
+    /// Source id carried by the events of this client, if it feeds a runner
+    pub source:         Option<SourceId>,
 }
 
 impl FFISafe for FFIClientHandle {
@@ -37,4 +39,18 @@ impl FFIClientHandle {
             Err(err) => FFIError::from(anyhow!(err)),
         }
     }
+
+    /// Source id carried by the events of this client, fails if the client
+    /// does not feed a runner
+    #[no_mangle]
+    pub extern "C" fn client_handle_source(client: *mut Self) -> FFIError<SourceId> {
+        let client = match Self::safe(client) {
+            Ok(x) => x,
+            Err(error) => return error,
+        };
+        match client.source {
+            Some(source) => FFIError::from_value(source),
+            None => FFIError::from(anyhow!("Client is not a source of a runner")),
+        }
+    }
 }
@@ -0,0 +1,51 @@
+use std::time::UNIX_EPOCH;
+
+use crate::{
+    ffi::{ffi_string, FFIArray, FFISEnvelope},
+    subscriber::{DeadLetter, SubscriberId},
+    SEnvelope,
+};
+
+/// Event a subscriber failed to handle
+#[repr(C)]
+pub struct FFIDeadLetter {
+    pub subscriber:   SubscriberId,
+    pub attempts:     u32,
+    /// When the last attempt failed, in nanoseconds since the Unix epoch
+    pub failed_at_ns: u64,
+    /// Error of the last attempt
+    pub error:        FFIArray<std::os::raw::c_uchar>,
+    pub envelope:     FFISEnvelope,
+}
+
+impl From<DeadLetter<SEnvelope>> for FFIDeadLetter {
+    fn from(letter: DeadLetter<SEnvelope>) -> Self {
+        let failed_at_ns = letter
+            .failed_at
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        Self {
+            subscriber: letter.subscriber,
+            attempts: letter.attempts,
+            failed_at_ns,
+            error: ffi_string(&letter.error),
+            envelope: FFISEnvelope::from(letter.event),
+        }
+    }
+}
+
+/// Free an array returned by `runner_dead_letters_inspect` or
+/// `runner_dead_letters_drain` along with the strings of its letters
+///
+/// # Safety
+/// The array must come from one of these functions and not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn destroy_dead_letters(letters: *mut FFIArray<FFIDeadLetter>) {
+    if letters.is_null() {
+        return;
+    }
+    for letter in Box::from_raw(letters).into_vec() {
+        drop(letter.error.into_vec());
+        drop(letter.envelope.event.into_event());
+    }
+}
@@ -1,4 +1,7 @@
-use std::{ffi::CString, os::raw::c_void};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_void,
+};
 
 use crate::ffi::{FFIArray, FFIError};
 
@@ -103,12 +106,21 @@ pub(crate) fn ffi_cstring(msg: &str, err_msg: &'static str) -> FFIError<std::os:
     }
 }
 
-pub(crate) fn ffi_string(msg: &str) -> FFIArray<std::os::raw::c_uchar> {
-    FFIArray::from(msg.as_bytes())
+/// Borrow a NUL terminated C string as UTF-8
+///
+/// # Safety
+/// Pointer should be null or respect `CStr::from_ptr` preconditions
+pub(crate) unsafe fn ffi_str<'a, T>(ptr: *const std::os::raw::c_char) -> Result<&'a str, FFIError<T>> {
+    if ptr.is_null() {
+        return Err(FFIError::from(anyhow::Error::msg("Null pointer provided as an argument")));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|error| FFIError::from(anyhow::Error::new(error)))
 }
 
-pub(crate) fn ffi_bytes_array(msg: &[u8]) -> FFIArray<std::os::raw::c_uchar> {
-    FFIArray::from(msg)
+pub(crate) fn ffi_string(msg: &str) -> FFIArray<std::os::raw::c_uchar> {
+    FFIArray::from(msg.as_bytes())
 }
 
 pub(crate) fn ffi_bytes_vec(msg: Vec<u8>) -> FFIArray<std::os::raw::c_uchar> {
@@ -0,0 +1,87 @@
+use tracing::{
+    span::{Attributes, Id, Record},
+    Event,
+    Subscriber,
+};
+use tracing_subscriber::{
+    field::RecordFields,
+    fmt::{
+        format::{DefaultFields, JsonFields, Pretty},
+        FormatFields,
+        FormattedFields,
+    },
+    layer::Context,
+    registry::{ExtensionsMut, LookupSpan},
+    Layer,
+};
+
+/// Formats the fields of every span for each field formatter of the formats,
+/// the fmt layer expects them on the spans opened before it was reloaded
+pub(crate) struct SpanFields;
+
+impl<S> Layer<S> for SpanFields
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut extensions = span.extensions_mut();
+        insert_fields(&mut extensions, DefaultFields::new(), attrs);
+        insert_fields(&mut extensions, JsonFields::new(), attrs);
+        insert_fields(&mut extensions, Pretty::default(), attrs);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut extensions = span.extensions_mut();
+        add_fields(&mut extensions, DefaultFields::new(), values);
+        add_fields(&mut extensions, JsonFields::new(), values);
+        add_fields(&mut extensions, Pretty::default(), values);
+    }
+}
+
+fn insert_fields<N, R>(extensions: &mut ExtensionsMut<'_>, formatter: N, fields: R)
+where
+    N: for<'w> FormatFields<'w> + 'static,
+    R: RecordFields,
+{
+    if extensions.get_mut::<FormattedFields<N>>().is_some() {
+        return;
+    }
+    let mut formatted = FormattedFields::<N>::new(String::new());
+    if formatter.format_fields(formatted.as_writer(), fields).is_ok() {
+        extensions.insert(formatted);
+    }
+}
+
+fn add_fields<N>(extensions: &mut ExtensionsMut<'_>, formatter: N, values: &Record<'_>)
+where
+    N: for<'w> FormatFields<'w> + 'static,
+{
+    match extensions.get_mut::<FormattedFields<N>>() {
+        Some(formatted) => {
+            let _ = formatter.add_fields(formatted, values);
+        },
+        None => insert_fields(extensions, formatter, values),
+    }
+}
+
+/// Only hands the events to the wrapped layer, the spans are formatted by
+/// [`SpanFields`] whatever the current format is
+pub(crate) struct EventsOnly<L>(pub(crate) L);
+
+impl<S, L> Layer<S> for EventsOnly<L>
+where
+    S: Subscriber,
+    L: Layer<S>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        self.0.on_event(event, ctx);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, PoisonError},
+};
+
+use anyhow::anyhow;
+use once_cell::sync::{Lazy, OnceCell};
+use tracing::{level_filters::LevelFilter, Event, Metadata};
+use tracing_core::{
+    callsite::Callsite,
+    field::{Field, FieldSet, Value},
+    identify_callsite,
+    subscriber::Interest,
+    Kind,
+};
+
+use crate::ffi::{ffi_str, FFIError, FFILogLevel, FFINull};
+
+/// Values of an event are given as a fixed size array, the message takes the
+/// first slot
+const MAX_FIELDS: usize = 32;
+
+/// Distinct level, target and field names given a callsite of their own,
+/// the events past them share one callsite per level
+const MAX_CALLSITES: usize = 1024;
+
+/// Target of the shared callsites, the target and the fields of the event
+/// are logged in the fields named by [`OVERFLOW_KEYS`]
+const OVERFLOW_TARGET: &str = "host";
+const OVERFLOW_KEYS: [&str; 2] = ["target", "fields"];
+
+/// Level, target and field names of the events logged by the host
+type CallsiteKey = (tracing::Level, String, Vec<String>);
+
+/// Callsites of the events logged by the host, the metadata of an event
+/// has to be static so they are kept until the process exits
+static HOST_CALLSITES: Lazy<Mutex<HashMap<CallsiteKey, &'static HostCallsite>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+struct HostCallsite {
+    metadata: OnceCell<Metadata<'static>>,
+}
+
+impl Callsite for HostCallsite {
+    fn set_interest(&self, _: Interest) {
+        // enabled is asked for every event instead
+    }
+
+    fn metadata(&self) -> &Metadata<'_> {
+        self.static_metadata()
+    }
+}
+
+impl HostCallsite {
+    /// Callsite of `level`, `target` and `keys`, registered the first time,
+    /// None once [`MAX_CALLSITES`] are registered
+    fn get(level: tracing::Level, target: &str, keys: &[&str]) -> Option<&'static HostCallsite> {
+        let mut callsites = HOST_CALLSITES.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (
+            level,
+            target.to_string(),
+            keys.iter().map(|key| key.to_string()).collect(),
+        );
+        if let Some(callsite) = callsites.get(&key) {
+            return Some(callsite);
+        }
+        if callsites.len() >= MAX_CALLSITES {
+            return None;
+        }
+        Some(Self::register(&mut callsites, key))
+    }
+
+    /// Callsite shared by the events of `level` which did not get one of
+    /// their own
+    fn overflow(level: tracing::Level) -> &'static HostCallsite {
+        let mut callsites = HOST_CALLSITES.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (
+            level,
+            OVERFLOW_TARGET.to_string(),
+            OVERFLOW_KEYS.iter().map(|key| key.to_string()).collect(),
+        );
+        match callsites.get(&key) {
+            Some(callsite) => callsite,
+            None => Self::register(&mut callsites, key),
+        }
+    }
+
+    fn register(
+        callsites: &mut HashMap<CallsiteKey, &'static HostCallsite>,
+        key: CallsiteKey,
+    ) -> &'static HostCallsite {
+        let (level, target, keys) = &key;
+        let leak = |text: &str| -> &'static str { Box::leak(text.to_string().into_boxed_str()) };
+        let names: Vec<&'static str> =
+            std::iter::once("message").chain(keys.iter().map(|key| leak(key))).collect();
+        let callsite: &'static HostCallsite = Box::leak(Box::new(HostCallsite {
+            metadata: OnceCell::new(),
+        }));
+        let fields = FieldSet::new(
+            Box::leak(names.into_boxed_slice()),
+            identify_callsite!(callsite),
+        );
+        let _ = callsite.metadata.set(Metadata::new(
+            "event from host",
+            leak(target),
+            *level,
+            None,
+            None,
+            None,
+            fields,
+            Kind::EVENT,
+        ));
+        tracing_core::callsite::register(callsite);
+        callsites.insert(key, callsite);
+        callsite
+    }
+
+    fn static_metadata(&self) -> &Metadata<'static> {
+        self.metadata.get().expect("Set before the callsite is registered")
+    }
+}
+
+/// Log `message` with `target` through the logger installed by
+/// `configure_logging` as if it was logged in Rust, inside the current span
+/// of the calling thread. `keys` and `values` hold `fields` strings each,
+/// at most 31 of them. The first 1024 distinct level, target and keys are
+/// kept in memory until the process exits, the events past them are logged
+/// with the `host` target, their target and fields going in the `target` and
+/// `fields` fields
+///
+/// # Safety
+/// Target, message, keys and values should be NUL terminated and respect
+/// `CStr::from_ptr` preconditions, keys and values should hold `fields`
+/// pointers each or be null when there are none
+#[no_mangle]
+pub unsafe extern "C" fn log_from_host(
+    level: FFILogLevel,
+    target: *const std::os::raw::c_char,
+    message: *const std::os::raw::c_char,
+    keys: *const *const std::os::raw::c_char,
+    values: *const *const std::os::raw::c_char,
+    fields: usize,
+) -> FFIError<FFINull> {
+    let target = match ffi_str(target) {
+        Ok(target) => target,
+        Err(error) => return error,
+    };
+    let message = match ffi_str(message) {
+        Ok(message) => message,
+        Err(error) => return error,
+    };
+    if fields >= MAX_FIELDS {
+        return FFIError::from(anyhow!(
+            "At most {} fields can be logged, got {}",
+            MAX_FIELDS - 1,
+            fields
+        ));
+    }
+    let (keys, values) = match (host_strings(keys, fields), host_strings(values, fields)) {
+        (Ok(keys), Ok(values)) => (keys, values),
+        (Err(error), _) | (_, Err(error)) => return error,
+    };
+    let mut seen = HashSet::new();
+    if let Some(key) =
+        keys.iter().find(|key| key.is_empty() || **key == "message" || !seen.insert(**key))
+    {
+        return FFIError::from(anyhow!("Invalid or repeated field name {:?}", key));
+    }
+
+    let level: tracing::Level = level.into();
+    if level > LevelFilter::current() {
+        return FFIError::from_value(FFINull);
+    }
+    let joined;
+    let (metadata, values) = match HostCallsite::get(level, target, &keys) {
+        Some(callsite) => (callsite.static_metadata(), values),
+        None => {
+            joined = keys
+                .iter()
+                .zip(&values)
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            (HostCallsite::overflow(level).static_metadata(), vec![target, joined.as_str()])
+        },
+    };
+    let names: Vec<Field> = metadata.fields().iter().collect();
+    let mut slots: [(&Field, Option<&dyn Value>); MAX_FIELDS] = [(&names[0], None); MAX_FIELDS];
+    slots[0].1 = Some(&message);
+    for (index, value) in values.iter().enumerate() {
+        slots[index + 1] = (&names[index + 1], Some(value));
+    }
+    let values = metadata.fields().value_set(&slots);
+    // unlike the macros dispatching an event does not ask the filter
+    tracing::dispatcher::get_default(|dispatch| {
+        if dispatch.enabled(metadata) {
+            dispatch.event(&Event::new(metadata, &values));
+        }
+    });
+    FFIError::from_value(FFINull)
+}
+
+/// # Safety
+/// Strings should hold `len` pointers respecting `CStr::from_ptr`
+/// preconditions, or be null when `len` is 0
+unsafe fn host_strings<'a>(
+    strings: *const *const std::os::raw::c_char,
+    len: usize,
+) -> Result<Vec<&'a str>, FFIError<FFINull>> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if strings.is_null() {
+        return Err(FFIError::from(anyhow!(
+            "Null pointer provided for the fields"
+        )));
+    }
+    std::slice::from_raw_parts(strings, len).iter().map(|string| ffi_str(*string)).collect()
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Condvar,
+        Mutex,
+        MutexGuard,
+        PoisonError,
+    },
+    thread::JoinHandle,
+};
+
+use anyhow::anyhow;
+
+use crate::ffi::{
+    ffi_log_record::LogRecord,
+    ffi_logging::{in_callback, install_logging, with_queue, Output, SharedOutput},
+    FFIError,
+    FFILogger,
+    FFILoggingConfig,
+    FFINull,
+};
+
+/// What happens to an event logged while the queue is full
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FFILogOverflow {
+    /// Drop the event being logged
+    FFILogOverflowDropNewest = 0,
+    /// Drop the oldest queued event to make room
+    FFILogOverflowDropOldest = 1,
+    /// Wait for the drain thread to make room
+    FFILogOverflowBlock = 2,
+}
+
+/// Log event waiting for the callback, already formatted for text loggers
+pub(crate) enum LogEntry {
+    Line(tracing::Level, Vec<u8>),
+    Record(LogRecord),
+}
+
+/// Event waiting in the queue, with the line for the sinks and the entry for
+/// the callback when the logger has them
+pub(crate) struct Queued {
+    pub(crate) line:  Vec<u8>,
+    pub(crate) entry: Option<LogEntry>,
+}
+
+#[derive(Default)]
+struct Entries {
+    queued: VecDeque<Queued>,
+    /// Set once the logger is uninstalled, nothing is queued anymore
+    closed: bool,
+}
+
+/// Bounded queue between the threads which log and the callback
+pub(crate) struct LogQueue {
+    capacity:     usize,
+    overflow:     FFILogOverflow,
+    entries:      Mutex<Entries>,
+    not_empty:    Condvar,
+    not_full:     Condvar,
+    dropped:      AtomicU64,
+    drain_thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LogQueue {
+    fn new(capacity: usize, overflow: FFILogOverflow) -> Self {
+        Self {
+            capacity,
+            overflow,
+            entries: Mutex::default(),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            dropped: AtomicU64::new(0),
+            drain_thread: Mutex::new(None),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn push(&self, entry: Queued) {
+        let mut entries = self.lock();
+        loop {
+            if entries.closed {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            if entries.queued.len() < self.capacity {
+                break;
+            }
+            match self.overflow {
+                FFILogOverflow::FFILogOverflowBlock => {
+                    entries = self.not_full.wait(entries).unwrap_or_else(PoisonError::into_inner);
+                },
+                FFILogOverflow::FFILogOverflowDropOldest => {
+                    entries.queued.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                },
+                FFILogOverflow::FFILogOverflowDropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                },
+            }
+        }
+        entries.queued.push_back(entry);
+        self.not_empty.notify_one();
+    }
+
+    /// Hand at most `max` queued events to the sinks and the handler of
+    /// `output` on the current thread, the lock is released during each call
+    pub(crate) fn drain(&self, output: &Output, max: usize) -> usize {
+        let mut delivered = 0;
+        while delivered < max {
+            let entry = match self.lock().queued.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            self.not_full.notify_one();
+            output.deliver(&entry.line, entry.entry);
+            delivered += 1;
+        }
+        delivered
+    }
+
+    /// Wait for the next event, None once the queue is closed and empty
+    fn next(&self) -> Option<Queued> {
+        let mut entries = self.lock();
+        loop {
+            if let Some(entry) = entries.queued.pop_front() {
+                self.not_full.notify_one();
+                return Some(entry);
+            }
+            if entries.closed {
+                return None;
+            }
+            entries = self.not_empty.wait(entries).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Hand the events to the sinks and the handler of `output` on a
+    /// dedicated thread until the queue is closed
+    pub(crate) fn start(self: &Arc<Self>, output: SharedOutput) -> std::io::Result<()> {
+        let queue = self.clone();
+        let drain_thread = std::thread::Builder::new()
+            .name(String::from("rust_py-log"))
+            .spawn(move || {
+                while let Some(entry) = queue.next() {
+                    let output = output.read().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(output) = output.as_ref() {
+                        output.deliver(&entry.line, entry.entry);
+                    }
+                }
+            })?;
+        *self.drain_thread.lock().unwrap_or_else(PoisonError::into_inner) = Some(drain_thread);
+        Ok(())
+    }
+
+    /// Stop queueing, wake the blocked threads and wait for the drain thread
+    /// to hand over what is left
+    pub(crate) fn close(&self) {
+        self.lock().closed = true;
+        self.not_full.notify_all();
+        self.not_empty.notify_all();
+        let drain_thread = self.drain_thread.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(drain_thread) = drain_thread {
+            let _ = drain_thread.join();
+        }
+    }
+}
+
+/// Install the logger like `configure_logging` except the threads which log
+/// only queue the events, up to `capacity` of them. They reach the callback
+/// and the sinks through a dedicated thread when `drain_thread` is set and
+/// through `drain_logs` otherwise. Waiting for room with `FFILogOverflowBlock`
+/// requires the drain thread
+#[no_mangle]
+pub extern "C" fn configure_buffered_logging(
+    config: FFILoggingConfig,
+    handler: *mut FFILogger,
+    capacity: usize,
+    overflow: FFILogOverflow,
+    drain_thread: bool,
+) -> FFIError<FFINull> {
+    let error = if capacity == 0 {
+        Some(anyhow!("The log queue capacity should be positive"))
+    } else if handler.is_null() {
+        Some(anyhow!("Buffered logging requires a logger"))
+    } else if overflow == FFILogOverflow::FFILogOverflowBlock && !drain_thread {
+        Some(anyhow!("Blocking on a full log queue requires the drain thread"))
+    } else {
+        None
+    };
+    if let Some(error) = error {
+        config.discard();
+        return FFIError::from(error);
+    }
+    let queue = LogQueue::new(capacity, overflow);
+    match install_logging(config, handler, Some((queue, drain_thread))) {
+        Ok(_) => FFIError::from_value(FFINull),
+        Err(error) => error,
+    }
+}
+
+/// Hand at most `max` queued log events to the callback and the sinks on the
+/// calling thread, 0 for the ones queued when called. Returns how many were
+/// handed
+#[no_mangle]
+pub extern "C" fn drain_logs(max: usize) -> FFIError<usize> {
+    if in_callback() {
+        return FFIError::from(anyhow!("Logs can not be drained from the log callback"));
+    }
+    let drained = with_queue(|queue, output| {
+        let max = if max == 0 { queue.lock().queued.len() } else { max };
+        queue.drain(output, max)
+    });
+    match drained {
+        Some(drained) => FFIError::from_value(drained),
+        None => FFIError::from(anyhow!("Buffered logging is not configured")),
+    }
+}
+
+/// Log events dropped because the queue was full since buffered logging was
+/// configured
+#[no_mangle]
+pub extern "C" fn dropped_logs() -> FFIError<u64> {
+    match with_queue(|queue, _| queue.dropped.load(Ordering::Relaxed)) {
+        Some(dropped) => FFIError::from_value(dropped),
+        None => FFIError::from(anyhow!("Buffered logging is not configured")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use super::*;
+
+    fn queued(line: &str) -> Queued {
+        Queued {
+            line:  line.as_bytes().to_vec(),
+            entry: None,
+        }
+    }
+
+    fn lines(queue: &LogQueue) -> Vec<String> {
+        let entries = queue.lock();
+        entries.queued.iter().map(|queued| String::from_utf8_lossy(&queued.line).into_owned()).collect()
+    }
+
+    /// Give the pusher time to block on the full queue, there is no way to
+    /// observe it
+    fn settle() {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_queued_events() {
+        let queue = LogQueue::new(2, FFILogOverflow::FFILogOverflowDropNewest);
+        for line in ["a", "b", "c", "d"] {
+            queue.push(queued(line));
+        }
+        assert_eq!(lines(&queue), ["a", "b"]);
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_latest_events() {
+        let queue = LogQueue::new(2, FFILogOverflow::FFILogOverflowDropOldest);
+        for line in ["a", "b", "c", "d"] {
+            queue.push(queued(line));
+        }
+        assert_eq!(lines(&queue), ["c", "d"]);
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn block_waits_for_room() {
+        let queue = Arc::new(LogQueue::new(1, FFILogOverflow::FFILogOverflowBlock));
+        queue.push(queued("a"));
+        let pusher = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push(queued("b")))
+        };
+        settle();
+        assert_eq!(lines(&queue), ["a"]);
+        assert!(!pusher.is_finished());
+
+        let first = queue.next().expect("queued event");
+        assert_eq!(first.line, b"a");
+        pusher.join().expect("pusher");
+        assert_eq!(lines(&queue), ["b"]);
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn close_wakes_blocked_pushers_and_hands_over_the_rest() {
+        let queue = Arc::new(LogQueue::new(1, FFILogOverflow::FFILogOverflowBlock));
+        queue.push(queued("a"));
+        let pusher = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push(queued("b")))
+        };
+        settle();
+        queue.close();
+        pusher.join().expect("pusher");
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 1);
+
+        // what was queued before closing is still handed over, then nothing
+        assert_eq!(queue.next().expect("queued event").line, b"a");
+        assert!(queue.next().is_none());
+        queue.push(queued("c"));
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn close_waits_for_the_drain_thread() {
+        let queue = Arc::new(LogQueue::new(8, FFILogOverflow::FFILogOverflowBlock));
+        for line in ["a", "b", "c"] {
+            queue.push(queued(line));
+        }
+        queue.start(SharedOutput::default()).expect("drain thread");
+        queue.close();
+        assert!(lines(&queue).is_empty());
+        assert!(queue.drain_thread.lock().unwrap_or_else(PoisonError::into_inner).is_none());
+    }
+}
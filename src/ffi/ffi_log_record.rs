@@ -0,0 +1,161 @@
+use std::{
+    fmt::Debug,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    Event,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan};
+
+use crate::ffi::{ffi_bytes_vec, FFIArray, FFILogLevel};
+
+/// Log event copied out of `tracing` so it can outlive the call which
+/// produced it
+#[derive(Debug, Clone)]
+pub(crate) struct LogRecord {
+    pub(crate) level:       tracing::Level,
+    pub(crate) timestamp:   SystemTime,
+    pub(crate) target:      String,
+    pub(crate) module_path: Option<String>,
+    pub(crate) file:        Option<String>,
+    pub(crate) line:        Option<u32>,
+    pub(crate) message:     String,
+    /// Names of the spans the event happened in, outermost first
+    pub(crate) spans:       Vec<String>,
+    pub(crate) fields:      Vec<(String, String)>,
+}
+
+impl LogRecord {
+    pub(crate) fn from_event<S>(event: &Event<'_>, ctx: &Context<'_, S>) -> Self
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let metadata = event.metadata();
+        let mut visitor = RecordVisitor::default();
+        event.record(&mut visitor);
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| scope.from_root().map(|span| span.name().to_string()).collect())
+            .unwrap_or_default();
+        Self {
+            level: *metadata.level(),
+            timestamp: SystemTime::now(),
+            target: metadata.target().to_string(),
+            module_path: metadata.module_path().map(String::from),
+            file: metadata.file().map(String::from),
+            line: metadata.line(),
+            message: visitor.message,
+            spans,
+            fields: visitor.fields,
+        }
+    }
+}
+
+/// Splits the `message` field from the others, strings are kept as is and
+/// the other values use their `Debug` representation
+#[derive(Default)]
+struct RecordVisitor {
+    message: String,
+    fields:  Vec<(String, String)>,
+}
+
+impl Visit for RecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.push(field, format!("{:?}", value));
+    }
+}
+
+impl RecordVisitor {
+    fn push(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = value,
+            name => self.fields.push((name.to_string(), value)),
+        }
+    }
+}
+
+/// Field of a [`FFILogRecord`]
+#[repr(C)]
+pub struct FFILogField {
+    pub key:   FFIArray<std::os::raw::c_uchar>,
+    pub value: FFIArray<std::os::raw::c_uchar>,
+}
+
+/// Structured log event given to the callback of a record logger, to free
+/// with `destroy_log_record`
+#[repr(C)]
+pub struct FFILogRecord {
+    pub level:        FFILogLevel,
+    /// When the event was logged, in nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+    pub target:       FFIArray<std::os::raw::c_uchar>,
+    /// Empty when unknown
+    pub module_path:  FFIArray<std::os::raw::c_uchar>,
+    /// Empty when unknown
+    pub file:         FFIArray<std::os::raw::c_uchar>,
+    /// 0 when unknown
+    pub line:         u32,
+    pub message:      FFIArray<std::os::raw::c_uchar>,
+    /// Names of the spans the event happened in, outermost first
+    pub spans:        FFIArray<FFIArray<std::os::raw::c_uchar>>,
+    pub fields:       FFIArray<FFILogField>,
+}
+
+impl From<LogRecord> for FFILogRecord {
+    fn from(record: LogRecord) -> Self {
+        let text = |text: String| ffi_bytes_vec(text.into_bytes());
+        let timestamp_ns = record
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        let spans: Vec<_> = record.spans.into_iter().map(text).collect();
+        let fields: Vec<_> = record
+            .fields
+            .into_iter()
+            .map(|(key, value)| FFILogField {
+                key:   text(key),
+                value: text(value),
+            })
+            .collect();
+        Self {
+            level: record.level.into(),
+            timestamp_ns,
+            target: text(record.target),
+            module_path: text(record.module_path.unwrap_or_default()),
+            file: text(record.file.unwrap_or_default()),
+            line: record.line.unwrap_or_default(),
+            message: text(record.message),
+            spans: FFIArray::from(spans),
+            fields: FFIArray::from(fields),
+        }
+    }
+}
+
+/// Free a record given to the callback of a record logger
+///
+/// # Safety
+/// The record must come from this library and not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn destroy_log_record(record: *mut FFILogRecord) {
+    if record.is_null() {
+        return;
+    }
+    let record = Box::from_raw(record);
+    drop(record.target.into_vec());
+    drop(record.module_path.into_vec());
+    drop(record.file.into_vec());
+    drop(record.message.into_vec());
+    for span in record.spans.into_vec() {
+        drop(span.into_vec());
+    }
+    for field in record.fields.into_vec() {
+        drop(field.key.into_vec());
+        drop(field.value.into_vec());
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+
+use crate::ffi::{ffi_bytes_vec, ffi_str, FFIArray, FFIError, FFILoggingConfig, FFINull, FFISafe};
+
+#[repr(u8)]
+pub enum FFILogSinkKind {
+    /// Standard error of the process
+    FFILogSinkStderr = 0,
+    /// File opened in append mode
+    FFILogSinkFile = 1,
+    /// File renamed with a numbered suffix once too big or too old
+    FFILogSinkRotatingFile = 2,
+}
+
+/// Output of the formatted lines added to a [`FFILoggingConfig`] besides the
+/// callback
+#[repr(C)]
+pub struct FFILogSink {
+    pub(crate) kind:         FFILogSinkKind,
+    /// Empty for stderr
+    pub(crate) path:         FFIArray<std::os::raw::c_uchar>,
+    /// Rotate before the file exceeds this size, 0 to ignore the size
+    pub(crate) max_bytes:    u64,
+    /// Rotate once the file was written for this long, 0 to ignore the age
+    pub(crate) max_age_secs: u64,
+    /// Rotated files kept from `path.1`, the newest, to `path.<retention>`
+    pub(crate) retention:    u32,
+}
+
+impl FFILogSink {
+    /// Open the output, the sink is consumed even on error
+    ///
+    /// # Safety
+    /// The path must come from `ffi_bytes_vec`
+    pub(crate) unsafe fn open(self) -> anyhow::Result<LogSink> {
+        let path = PathBuf::from(String::from_utf8(self.path.into_vec())?);
+        let sink = match self.kind {
+            FFILogSinkKind::FFILogSinkStderr => LogSink::Stderr,
+            FFILogSinkKind::FFILogSinkFile => LogSink::File(Mutex::new(append(&path)?)),
+            FFILogSinkKind::FFILogSinkRotatingFile => {
+                let max_age =
+                    (self.max_age_secs > 0).then(|| Duration::from_secs(self.max_age_secs));
+                LogSink::Rotating(Mutex::new(RotatingFile::open(
+                    path,
+                    self.max_bytes,
+                    max_age,
+                    self.retention,
+                )?))
+            },
+        };
+        Ok(sink)
+    }
+
+    /// Free the sink without opening it
+    ///
+    /// # Safety
+    /// The path must come from `ffi_bytes_vec`
+    pub(crate) unsafe fn discard(self) {
+        drop(self.path.into_vec());
+    }
+}
+
+impl FFILoggingConfig {
+    /// Free the directives and the sinks of a config which is not installed
+    pub(crate) fn discard(self) {
+        // the config owns both arrays since create_logger_config
+        unsafe {
+            drop(self.directives.into_vec());
+            for sink in self.sinks.into_vec() {
+                sink.discard();
+            }
+        }
+    }
+
+    fn add_sink(&mut self, sink: FFILogSink) {
+        let sinks = std::mem::replace(&mut self.sinks, FFIArray::from(Vec::new()));
+        // the config owns the sinks since create_logger_config
+        let mut sinks = unsafe { sinks.into_vec() };
+        sinks.push(sink);
+        self.sinks = FFIArray::from(sinks);
+    }
+}
+
+/// Also write the formatted lines to the standard error
+#[no_mangle]
+pub extern "C" fn logger_config_add_stderr(config: *mut FFILoggingConfig) -> FFIError<FFINull> {
+    let config = match FFILoggingConfig::safe(config) {
+        Ok(config) => config,
+        Err(error) => return error,
+    };
+    config.add_sink(FFILogSink {
+        kind:         FFILogSinkKind::FFILogSinkStderr,
+        path:         ffi_bytes_vec(Vec::new()),
+        max_bytes:    0,
+        max_age_secs: 0,
+        retention:    0,
+    });
+    FFIError::from_value(FFINull)
+}
+
+/// Also append the formatted lines to the file at `path`, created if missing
+///
+/// # Safety
+/// Path should be NUL terminated and respect `CStr::from_ptr` preconditions
+#[no_mangle]
+pub unsafe extern "C" fn logger_config_add_file(
+    config: *mut FFILoggingConfig,
+    path: *const std::os::raw::c_char,
+) -> FFIError<FFINull> {
+    logger_config_add_rotating_file(config, path, 0, 0, 0)
+}
+
+/// Also append the formatted lines to the file at `path`, which is renamed
+/// to `path.1` before it exceeds `max_bytes` or once it was written for
+/// `max_age_secs`. The older files shift to the next suffix and only
+/// `retention` of them are kept. Without limits this is a plain file
+///
+/// # Safety
+/// Path should be NUL terminated and respect `CStr::from_ptr` preconditions
+#[no_mangle]
+pub unsafe extern "C" fn logger_config_add_rotating_file(
+    config: *mut FFILoggingConfig,
+    path: *const std::os::raw::c_char,
+    max_bytes: u64,
+    max_age_secs: u64,
+    retention: u32,
+) -> FFIError<FFINull> {
+    let config = match FFILoggingConfig::safe(config) {
+        Ok(config) => config,
+        Err(error) => return error,
+    };
+    let path = match ffi_str(path) {
+        Ok(path) => path,
+        Err(error) => return error,
+    };
+    if path.is_empty() {
+        return FFIError::from(anyhow!("The log file path should not be empty"));
+    }
+    let kind = if max_bytes == 0 && max_age_secs == 0 {
+        FFILogSinkKind::FFILogSinkFile
+    } else {
+        FFILogSinkKind::FFILogSinkRotatingFile
+    };
+    config.add_sink(FFILogSink {
+        kind,
+        path: ffi_bytes_vec(path.as_bytes().to_vec()),
+        max_bytes,
+        max_age_secs,
+        retention,
+    });
+    FFIError::from_value(FFINull)
+}
+
+/// Opened output of a [`FFILogSink`], written on the thread which logged
+pub(crate) enum LogSink {
+    Stderr,
+    File(Mutex<File>),
+    Rotating(Mutex<RotatingFile>),
+}
+
+impl LogSink {
+    pub(crate) fn write(&self, line: &[u8]) {
+        // the logger has nowhere to report its own failures
+        let _ = match self {
+            LogSink::Stderr => std::io::stderr().lock().write_all(line),
+            LogSink::File(file) => {
+                file.lock().unwrap_or_else(PoisonError::into_inner).write_all(line)
+            },
+            LogSink::Rotating(file) => {
+                file.lock().unwrap_or_else(PoisonError::into_inner).write(line)
+            },
+        };
+    }
+}
+
+pub(crate) struct RotatingFile {
+    path:      PathBuf,
+    file:      File,
+    size:      u64,
+    opened:    Instant,
+    max_bytes: u64,
+    max_age:   Option<Duration>,
+    retention: u32,
+}
+
+impl RotatingFile {
+    fn open(
+        path: PathBuf,
+        max_bytes: u64,
+        max_age: Option<Duration>,
+        retention: u32,
+    ) -> std::io::Result<Self> {
+        let file = append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened: Instant::now(),
+            max_bytes,
+            max_age,
+            retention,
+        })
+    }
+
+    fn write(&mut self, line: &[u8]) -> std::io::Result<()> {
+        if self.size > 0 && self.should_rotate(line.len() as u64) {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self, len: u64) -> bool {
+        let too_big = self.max_bytes > 0 && self.size + len > self.max_bytes;
+        let too_old = matches!(self.max_age, Some(max_age) if self.opened.elapsed() >= max_age);
+        too_big || too_old
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.retention == 0 {
+            self.file.set_len(0)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated(self.retention));
+            for index in (1..self.retention).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    std::fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated(1))?;
+            self.file = append(&self.path)?;
+        }
+        self.size = 0;
+        self.opened = Instant::now();
+        Ok(())
+    }
+
+    fn rotated(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+}
+
+fn append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty directory of its own for each test
+    fn dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rust_py-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        dir
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).expect("log file")
+    }
+
+    fn write_lines(file: &mut RotatingFile, count: usize) {
+        for index in 0..count {
+            file.write(format!("line{}\n", index).as_bytes()).expect("write");
+        }
+    }
+
+    #[test]
+    fn rotates_by_size_up_to_the_retention() {
+        let dir = dir("rotate-size");
+        let path = dir.join("app.log");
+        let mut file = RotatingFile::open(path.clone(), 10, None, 2).expect("open");
+        write_lines(&mut file, 5);
+        assert_eq!(read(&path), "line4\n");
+        assert_eq!(read(&file.rotated(1)), "line3\n");
+        assert_eq!(read(&file.rotated(2)), "line2\n");
+        assert!(!file.rotated(3).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn truncates_without_retention() {
+        let dir = dir("rotate-truncate");
+        let path = dir.join("app.log");
+        let mut file = RotatingFile::open(path.clone(), 10, None, 0).expect("open");
+        write_lines(&mut file, 3);
+        assert_eq!(read(&path), "line2\n");
+        assert!(!file.rotated(1).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rotates_by_age() {
+        let dir = dir("rotate-age");
+        let path = dir.join("app.log");
+        let mut file =
+            RotatingFile::open(path.clone(), 0, Some(Duration::from_millis(50)), 1).expect("open");
+        file.write(b"old\n").expect("write");
+        file.write(b"still young\n").expect("write");
+        std::thread::sleep(Duration::from_millis(60));
+        file.write(b"new\n").expect("write");
+        assert_eq!(read(&path), "new\n");
+        assert_eq!(read(&file.rotated(1)), "old\nstill young\n");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn keeps_appending_to_an_existing_file() {
+        let dir = dir("rotate-reopen");
+        let path = dir.join("app.log");
+        std::fs::write(&path, "before\n").expect("existing file");
+        let mut file = RotatingFile::open(path.clone(), 10, None, 1).expect("open");
+        // the size of the existing content counts towards the limit
+        file.write(b"after\n").expect("write");
+        assert_eq!(read(&path), "after\n");
+        assert_eq!(read(&file.rotated(1)), "before\n");
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -3,32 +3,109 @@ use anyhow::anyhow;
 /// thread local Use tokio::tracing under the hood to provide dimensional
 /// logging through all the crates
 use std::borrow::Borrow;
+use std::cell::Cell;
 use std::panic::catch_unwind;
-
-use tracing::{
-    span::{Attributes, Record},
-    Event,
-    Id,
-    Metadata,
-};
-
-use tracing_subscriber::fmt::{
-    time::{SystemTime, Uptime, UtcTime},
-    MakeWriter,
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::ThreadId;
+
+use once_cell::sync::{Lazy, OnceCell};
+use tracing::{dispatcher::DefaultGuard, Event};
+
+use tracing_subscriber::{
+    filter::{Directive, EnvFilter, LevelFilter},
+    fmt::{
+        time::{SystemTime, Uptime, UtcTime},
+        MakeWriter,
+    },
+    layer::{Context, Layered, SubscriberExt},
+    registry::{LookupSpan, Registry},
+    reload,
+    Layer,
 };
 
 use crate::ffi::{
+    ffi_log_fields::{EventsOnly, SpanFields},
+    ffi_log_queue::{LogEntry, LogQueue, Queued},
+    ffi_log_record::LogRecord,
+    ffi_log_sink::LogSink,
     create_heap_pointer,
-    ffi_bytes_array,
+    ffi_bytes_vec,
+    ffi_str,
     ffi_string,
     FFIArray,
     FFIError,
+    FFILogRecord,
+    FFILogSink,
     FFINull,
+    FFISafe,
     FFISafeMove,
 };
 
 thread_local! {
    pub static LOG_BUFFER: std::cell::RefCell<Vec<u8>> = std::cell::RefCell::new(Vec::new());
+   /// Set while the thread runs the host callback, the events it logs are
+   /// dropped and it can not reconfigure logging
+   static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Filter of the logger installed by `configure_logging`, None before and
+/// once uninstalled
+static LOG_FILTER: Lazy<Mutex<Option<LogFilter>>> = Lazy::new(|| Mutex::new(None));
+
+/// Subscriber set as the global default by the first `configure_logging`,
+/// tracing only accepts one so the later ones reload it
+static GLOBAL_LOGGER: OnceCell<GlobalLogger> = OnceCell::new();
+
+/// Held while the global logger is configured or uninstalled
+static INSTALL: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+type FilteredRegistry = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+type FormatLayer = Box<dyn Layer<FilteredRegistry> + Send + Sync>;
+
+/// Output of a logger, emptied to stop calling the host
+pub(crate) type SharedOutput = Arc<RwLock<Option<Output>>>;
+
+struct GlobalLogger {
+    filter: reload::Handle<EnvFilter, Registry>,
+    format: reload::Handle<FormatLayer, FilteredRegistry>,
+    output: SharedOutput,
+}
+
+/// Default level and per target directives of the installed logger, the
+/// filter is rebuilt from both whenever one of them changes
+struct LogFilter {
+    level:      LevelFilter,
+    directives: Vec<Directive>,
+}
+
+impl LogFilter {
+    fn lock() -> MutexGuard<'static, Option<LogFilter>> {
+        LOG_FILTER.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn env_filter(level: LevelFilter, directives: &[Directive]) -> EnvFilter {
+        directives
+            .iter()
+            .cloned()
+            .fold(EnvFilter::default().add_directive(level.into()), EnvFilter::add_directive)
+    }
+
+    fn reload(&self) -> anyhow::Result<()> {
+        reload_filter(Self::env_filter(self.level, &self.directives))
+    }
+}
+
+fn reload_filter(filter: EnvFilter) -> anyhow::Result<()> {
+    match GLOBAL_LOGGER.get() {
+        Some(global) => global.filter.reload(filter).map_err(|e| anyhow!(e)),
+        None => Err(anyhow!("Logging is not configured")),
+    }
+}
+
+/// Whether the current thread runs the host callback
+pub(crate) fn in_callback() -> bool {
+    IN_CALLBACK.with(Cell::get)
 }
 
 #[repr(u8)]
@@ -91,20 +168,31 @@ pub struct FFILoggingConfig {
     pub(crate) show_level: bool,
     /// Show where was the log issued in Rust
     pub(crate) show_trace: bool,
+    /// Filter directives in the `RUST_LOG` syntax, empty to use `RUST_LOG`
+    pub(crate) directives: FFIArray<std::os::raw::c_uchar>,
+    /// Outputs of the formatted lines besides the callback, added with the
+    /// `logger_config_add_*` functions
+    pub(crate) sinks: FFIArray<FFILogSink>,
 }
 
+impl FFISafe for FFILoggingConfig {}
+
+/// Exactly one of the callbacks is set, either given the formatted line of
+/// each log event or a record to free with `destroy_log_record`
 #[repr(C)]
 pub struct FFILogger {
-    callback: extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>),
+    callback:        Option<extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>)>,
+    record_callback: Option<extern "C" fn(*mut FFILogRecord)>,
 }
 
 impl FFILogger {
     /// Tested callback assigned directly to the logger
     #[no_mangle]
-    pub extern "C" fn create_logger(
-        callback: extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>),
-    ) -> FFIError<Self> {
-        let logger = Self { callback };
+    pub extern "C" fn create_logger(callback: extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>)) -> FFIError<Self> {
+        let logger = Self {
+            callback:        Some(callback),
+            record_callback: None,
+        };
         match catch_unwind(|| {
             logger.on_message(
                 FFILogLevel::FFILogLevelInfo,
@@ -121,48 +209,454 @@ impl FFILogger {
 
     /// Untested callback assigned directly to the logger, will fail later on
     #[no_mangle]
-    pub extern "C" fn create_unsafe_logger(
-        callback: extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>),
-    ) -> Self {
-        FFILogger { callback }
+    pub extern "C" fn create_unsafe_logger(callback: extern "C" fn(FFILogLevel, *mut FFIArray<std::os::raw::c_uchar>)) -> Self {
+        FFILogger {
+            callback:        Some(callback),
+            record_callback: None,
+        }
+    }
+
+    /// Tested callback given structured records instead of formatted lines,
+    /// the configured format only applies to text loggers
+    #[no_mangle]
+    pub extern "C" fn create_record_logger(callback: extern "C" fn(*mut FFILogRecord)) -> FFIError<Self> {
+        let logger = Self {
+            callback:        None,
+            record_callback: Some(callback),
+        };
+        let record = LogRecord {
+            level:       tracing::Level::INFO,
+            timestamp:   std::time::SystemTime::now(),
+            target:      String::from(module_path!()),
+            module_path: Some(String::from(module_path!())),
+            file:        Some(String::from(file!())),
+            line:        Some(line!()),
+            message:     String::from("Starting logger : testing callback"),
+            spans:       Vec::new(),
+            fields:      Vec::new(),
+        };
+        match catch_unwind(|| logger.on_record(record)) {
+            Ok(_) => FFIError::from_value(logger),
+            Err(e) => FFIError::from(anyhow::Error::msg(format!(
+                "Panicked when testing callback with error :\n {:?}",
+                e
+            ))),
+        }
     }
 
     fn on_message(&self, level: FFILogLevel, message: FFIArray<std::os::raw::c_uchar>) {
-        (self.callback)(level, create_heap_pointer(message));
+        match self.callback {
+            Some(callback) => callback(level, create_heap_pointer(message)),
+            None => drop(unsafe { message.into_vec() }),
+        }
+    }
+
+    fn on_record(&self, record: LogRecord) {
+        if let Some(callback) = self.record_callback {
+            callback(create_heap_pointer(FFILogRecord::from(record)));
+        }
+    }
+
+    pub(crate) fn deliver(&self, entry: LogEntry) {
+        let previous = IN_CALLBACK.with(|in_callback| in_callback.replace(true));
+        match entry {
+            LogEntry::Line(level, line) => self.on_message(level.into(), ffi_bytes_vec(line)),
+            LogEntry::Record(record) => self.on_record(record),
+        }
+        IN_CALLBACK.with(|in_callback| in_callback.set(previous));
     }
 }
 
 impl FFISafeMove for FFILogger {}
 
+/// Directives such as `tokio=warn,rust_py=trace` filter the logs per target
+/// in the `RUST_LOG` syntax, a bare level replaces `level`. When null or
+/// empty the `RUST_LOG` environment variable is used if set
+///
+/// # Safety
+/// Directives should be null or NUL terminated and respect `CStr::from_ptr`
+/// preconditions
 #[no_mangle]
-pub extern "C" fn create_logger_config(
+pub unsafe extern "C" fn create_logger_config(
     level: FFILogLevel,
     time_format: FFILogTimeFormat,
     log_format: FFILogFormat,
     show_level: bool,
     show_trace: bool,
+    directives: *const std::os::raw::c_char,
 ) -> FFILoggingConfig {
+    let directives = if directives.is_null() {
+        Vec::new()
+    } else {
+        std::ffi::CStr::from_ptr(directives).to_bytes().to_vec()
+    };
     FFILoggingConfig {
         level,
         time_format,
         log_format,
         show_level,
         show_trace,
+        directives: ffi_bytes_vec(directives),
+        sinks: FFIArray::from(Vec::new()),
+    }
+}
+
+/// Install the logger calling `handler` on the thread which logged, the
+/// handler may be null when the config has sinks. A logger installed before
+/// is uninstalled first. The handler is only taken once the logger is
+/// installed, the caller still owns it when an error is returned
+#[no_mangle]
+pub extern "C" fn configure_logging(config: FFILoggingConfig, handler: *mut FFILogger) -> FFIError<FFINull> {
+    match install_logging(config, handler, None) {
+        Ok(_) => FFIError::from_value(FFINull),
+        Err(error) => error,
+    }
+}
+
+/// Install the global logger with the filter, format and outputs of
+/// `config`, events are queued when `queue` is set and handed over by a
+/// drain thread if asked
+pub(crate) fn install_logging(
+    config: FFILoggingConfig,
+    mut handler: *mut FFILogger,
+    queue: Option<(LogQueue, bool)>,
+) -> Result<(), FFIError<FFINull>> {
+    if in_callback() {
+        config.discard();
+        return Err(FFIError::from(anyhow!(
+            "Logging can not be configured from the log callback"
+        )));
+    }
+    let parts = LoggerParts::new(config, handler)?;
+    let _install = INSTALL.lock().unwrap_or_else(PoisonError::into_inner);
+    let filter = LogFilter::env_filter(parts.level, &parts.directives);
+    let queue = queue.map(|(queue, drain_thread)| (Arc::new(queue), drain_thread));
+    // the drain thread stops with its queue when the logger can not be set
+    let abort = |e: anyhow::Error| {
+        if let Some((queue, _)) = &queue {
+            queue.close();
+        }
+        FFIError::from(e)
+    };
+    let output = match GLOBAL_LOGGER.get() {
+        Some(global) => {
+            start_drain(&queue, &global.output)?;
+            drop(take_output(&global.output));
+            global.filter.reload(filter).map_err(|e| abort(anyhow!(e)))?;
+            global.format.reload(parts.format).map_err(|e| abort(anyhow!(e)))?;
+            global.output.clone()
+        },
+        None => {
+            let (subscriber, logger) = subscriber(filter, parts.format);
+            start_drain(&queue, &logger.output)?;
+            tracing::subscriber::set_global_default(subscriber).map_err(|e| abort(anyhow!(e)))?;
+            let output = logger.output.clone();
+            let _ = GLOBAL_LOGGER.set(logger);
+            output
+        },
+    };
+    // nothing fails from here, the caller keeps the handler otherwise
+    *output.write().unwrap_or_else(PoisonError::into_inner) = Some(Output {
+        handler: take_handler(&mut handler),
+        queue:   queue.map(|(queue, _)| queue),
+        sinks:   parts.sinks,
+    });
+    *LogFilter::lock() = Some(LogFilter {
+        level:      parts.level,
+        directives: parts.directives,
+    });
+    Ok(())
+}
+
+/// Start the drain thread of `queue` when asked, before the logger is set so
+/// nothing is queued when it can not start
+fn start_drain(queue: &Option<(Arc<LogQueue>, bool)>, output: &SharedOutput) -> Result<(), FFIError<FFINull>> {
+    match queue {
+        Some((queue, true)) => queue.start(output.clone()).map_err(|e| {
+            FFIError::from(anyhow!("The log drain thread did not start: {}", e))
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Stop calling the host callback, the calls in progress and the events
+/// left in the queue are handed over first. Logging can be configured again
+/// afterwards
+#[no_mangle]
+pub extern "C" fn uninstall_logging() -> FFIError<FFINull> {
+    if in_callback() {
+        return FFIError::from(anyhow!(
+            "Logging can not be uninstalled from the log callback"
+        ));
     }
+    let _install = INSTALL.lock().unwrap_or_else(PoisonError::into_inner);
+    let output = match GLOBAL_LOGGER.get().and_then(|global| take_output(&global.output)) {
+        Some(output) => output,
+        None => return FFIError::from(anyhow!("Logging is not configured")),
+    };
+    drop(output);
+    *LogFilter::lock() = None;
+    match reload_filter(EnvFilter::default().add_directive(LevelFilter::OFF.into())) {
+        Ok(_) => FFIError::from_value(FFINull),
+        Err(e) => FFIError::from(e),
+    }
+}
+
+/// Logger installed by `configure_scoped_logging` for a single thread
+pub struct FFILoggingScope {
+    output: SharedOutput,
+    thread: ThreadId,
+    _guard: DefaultGuard,
 }
 
+impl FFISafeMove for FFILoggingScope {}
+
+/// Install a logger for the calling thread only until `exit_logging_scope`,
+/// the other threads keep the global one. Its filter is not changed by
+/// `set_log_level` and `set_log_directives`
 #[no_mangle]
-pub extern "C" fn configure_logging(
+pub extern "C" fn configure_scoped_logging(
     config: FFILoggingConfig,
     mut handler: *mut FFILogger,
-) -> FFIError<FFINull> {
-    let handler = match FFILogger::safe_move(&mut handler) {
-        Ok(x) => x,
+) -> FFIError<FFILoggingScope> {
+    if in_callback() {
+        config.discard();
+        return FFIError::from(anyhow!(
+            "Logging can not be configured from the log callback"
+        ));
+    }
+    let parts = match LoggerParts::new(config, handler) {
+        Ok(parts) => parts,
         Err(error) => return error,
     };
-    match tracing::subscriber::set_global_default(adapter(config, handler)) {
+    let (subscriber, logger) = subscriber(
+        LogFilter::env_filter(parts.level, &parts.directives),
+        parts.format,
+    );
+    *logger.output.write().unwrap_or_else(PoisonError::into_inner) = Some(Output {
+        handler: take_handler(&mut handler),
+        queue:   None,
+        sinks:   parts.sinks,
+    });
+    FFIError::from_value(FFILoggingScope {
+        output: logger.output,
+        thread: std::thread::current().id(),
+        _guard: tracing::subscriber::set_default(subscriber),
+    })
+}
+
+/// Stop calling the callback of a scoped logger and restore the logger the
+/// thread used before, on the thread which created the scope
+///
+/// # Safety
+/// The scope must come from `configure_scoped_logging` and not be used
+/// afterwards
+#[no_mangle]
+pub unsafe extern "C" fn exit_logging_scope(mut scope: *mut FFILoggingScope) -> FFIError<FFINull> {
+    if in_callback() {
+        return FFIError::from(anyhow!(
+            "A logging scope can not be exited from the log callback"
+        ));
+    }
+    match scope.as_ref() {
+        Some(scope) if scope.thread != std::thread::current().id() => {
+            return FFIError::from(anyhow!(
+                "A logging scope should be exited on the thread which created it"
+            ))
+        },
+        _ => {},
+    }
+    let scope = match FFILoggingScope::safe_move(&mut scope) {
+        Ok(scope) => scope,
+        Err(error) => return error,
+    };
+    drop(take_output(&scope.output));
+    FFIError::from_value(FFINull)
+}
+
+/// Format, filter and outputs read from a config, the handler is left to the
+/// caller until the logger is installed
+struct LoggerParts {
+    format:     FormatLayer,
+    level:      LevelFilter,
+    directives: Vec<Directive>,
+    sinks:      Vec<LogSink>,
+}
+
+impl LoggerParts {
+    fn new<T>(config: FFILoggingConfig, handler: *mut FFILogger) -> Result<Self, FFIError<T>> {
+        let format = config.build();
+        let level: tracing::Level = config.level.borrow().into();
+        let mut level = LevelFilter::from_level(level);
+        // the config owns both arrays since create_logger_config, they are
+        // taken before any validation so they are freed whatever happens
+        let directives = String::from_utf8_lossy(&unsafe { config.directives.into_vec() }).into_owned();
+        let sinks = unsafe { config.sinks.into_vec() };
+        let directives = match directives.trim() {
+            "" => std::env::var(EnvFilter::DEFAULT_ENV).unwrap_or_default(),
+            _ => directives,
+        };
+        let directives = match parse_directives(&directives, &mut level) {
+            Ok(directives) => directives,
+            Err(e) => {
+                for sink in sinks {
+                    unsafe { sink.discard() };
+                }
+                return Err(FFIError::from(e));
+            },
+        };
+        // every sink is opened before looking at errors so all of them are freed
+        let sinks: Vec<_> = sinks.into_iter().map(|sink| unsafe { sink.open() }).collect();
+        let sinks = sinks
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(FFIError::from)?;
+        if handler.is_null() && sinks.is_empty() {
+            return Err(FFIError::from(anyhow!(
+                "A logger is required when the config has no sinks"
+            )));
+        }
+        Ok(Self {
+            format,
+            level,
+            directives,
+            sinks,
+        })
+    }
+}
+
+/// Take the handler once the logger using it is installed, null when the
+/// config only has sinks
+fn take_handler(handler: &mut *mut FFILogger) -> Option<FFILogger> {
+    FFILogger::safe_move::<FFINull>(handler).ok()
+}
+
+/// Subscriber whose filter and format can be reloaded and whose output
+/// starts empty
+fn subscriber(
+    filter: EnvFilter,
+    format: FormatLayer,
+) -> (impl tracing::Subscriber + Send + Sync, GlobalLogger) {
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    let (format, format_handle) = reload::Layer::new(format);
+    let output = SharedOutput::default();
+    let subscriber = Registry::default()
+        .with(filter)
+        .with(format)
+        .with(SpanFields)
+        .with(Adapter {
+            output: output.clone(),
+        });
+    let logger = GlobalLogger {
+        filter: filter_handle,
+        format: format_handle,
+        output,
+    };
+    (subscriber, logger)
+}
+
+/// Empty `output` once the queued events are handed over, waiting for the
+/// callbacks in progress
+fn take_output(output: &RwLock<Option<Output>>) -> Option<Output> {
+    let queue = output
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .and_then(|output| output.queue.clone());
+    if let Some(queue) = queue {
+        queue.close();
+    }
+    let output = output.write().unwrap_or_else(PoisonError::into_inner).take();
+    // without a drain thread the events left wait for drain_logs
+    if let Some(output @ Output { queue: Some(queue), .. }) = &output {
+        queue.drain(output, usize::MAX);
+    }
+    output
+}
+
+/// Call `f` with the queue and the output of the global logger when it is
+/// buffered
+pub(crate) fn with_queue<T>(f: impl FnOnce(&LogQueue, &Output) -> T) -> Option<T> {
+    let output = GLOBAL_LOGGER.get()?.output.read().unwrap_or_else(PoisonError::into_inner);
+    let output = output.as_ref()?;
+    Some(f(output.queue.as_ref()?, output))
+}
+
+/// Change the default level of the logger installed by `configure_logging`,
+/// the per target directives are kept
+#[no_mangle]
+pub extern "C" fn set_log_level(level: FFILogLevel) -> FFIError<FFINull> {
+    let mut filter = LogFilter::lock();
+    let filter = match filter.as_mut() {
+        Some(filter) => filter,
+        None => return FFIError::from(anyhow!("Logging is not configured")),
+    };
+    let level: tracing::Level = level.into();
+    filter.level = LevelFilter::from_level(level);
+    match filter.reload() {
+        Ok(_) => FFIError::from_value(FFINull),
+        Err(e) => FFIError::from(e),
+    }
+}
+
+/// Replace the per target directives of the logger installed by
+/// `configure_logging`, such as `rust_py::subscriber=debug,tokio=warn`. An
+/// empty string removes them and a bare level changes the default level,
+/// nothing changes if one is invalid
+///
+/// # Safety
+/// Directives should be NUL terminated and respect `CStr::from_ptr`
+/// preconditions
+#[no_mangle]
+pub unsafe extern "C" fn set_log_directives(directives: *const std::os::raw::c_char) -> FFIError<FFINull> {
+    let directives = match ffi_str(directives) {
+        Ok(directives) => directives,
+        Err(error) => return error,
+    };
+    let mut filter = LogFilter::lock();
+    let filter = match filter.as_mut() {
+        Some(filter) => filter,
+        None => return FFIError::from(anyhow!("Logging is not configured")),
+    };
+    let mut level = filter.level;
+    filter.directives = match parse_directives(directives, &mut level) {
+        Ok(directives) => directives,
+        Err(e) => return FFIError::from(e),
+    };
+    filter.level = level;
+    match filter.reload() {
         Ok(_) => FFIError::from_value(FFINull),
-        Err(e) => FFIError::from(anyhow!(e)),
+        Err(e) => FFIError::from(e),
+    }
+}
+
+/// Split comma separated directives in the `RUST_LOG` syntax, a bare level
+/// such as `debug` replaces `level` instead of becoming a directive
+fn parse_directives(directives: &str, level: &mut LevelFilter) -> anyhow::Result<Vec<Directive>> {
+    let mut parsed = Vec::new();
+    for directive in directives.split(',').map(str::trim).filter(|directive| !directive.is_empty()) {
+        match directive.parse::<LevelFilter>() {
+            Ok(bare) => *level = bare,
+            Err(_) => parsed.push(
+                directive
+                    .parse()
+                    .map_err(|e| anyhow!("Invalid log directive {}: {}", directive, e))?,
+            ),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Current filter of the logger installed by `configure_logging` in the
+/// directive syntax, such as `rust_py::subscriber=debug,info`
+#[no_mangle]
+pub extern "C" fn log_filter() -> FFIError<FFIArray<std::os::raw::c_uchar>> {
+    match LogFilter::lock().as_ref() {
+        Some(filter) => {
+            let text = LogFilter::env_filter(filter.level, &filter.directives).to_string();
+            FFIError::from_value(ffi_string(&text))
+        },
+        None => FFIError::from(anyhow!("Logging is not configured")),
     }
 }
 
@@ -189,23 +683,14 @@ impl std::io::Write for ThreadLocalBufferWriter {
     }
 }
 
-fn adapter(
-    config: FFILoggingConfig,
-    handler: FFILogger,
-) -> impl tracing::Subscriber + Send + Sync + 'static {
-    Adapter {
-        handler,
-        inner: config.build(),
-    }
-}
-
 impl FFILoggingConfig {
-    fn build(&self) -> Box<dyn tracing::Subscriber + Send + Sync> {
-        let level: tracing::Level = self.level.borrow().into();
+    fn build(&self) -> FormatLayer {
+        Box::new(EventsOnly(self.fmt_layer()))
+    }
 
-        let builder = tracing_subscriber::fmt()
+    fn fmt_layer(&self) -> FormatLayer {
+        let builder = tracing_subscriber::fmt::layer()
             .with_ansi(false)
-            .with_max_level(level)
             .with_level(self.show_level)
             .with_target(self.show_trace)
             .with_writer(ThreadLocalMakeWriter);
@@ -213,90 +698,98 @@ impl FFILoggingConfig {
             FFILogTimeFormat::FFILogTimeFormatNone => {
                 let builder = builder.without_time();
                 match self.log_format {
-                    FFILogFormat::FFILogFormatText => Box::new(builder.finish()),
-                    FFILogFormat::FFILogFormatJson => Box::new(builder.json().finish()),
-                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact().finish()),
-                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty().finish()),
+                    FFILogFormat::FFILogFormatText => Box::new(builder),
+                    FFILogFormat::FFILogFormatJson => Box::new(builder.json()),
+                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact()),
+                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty()),
                 }
             }
             FFILogTimeFormat::FFILogTimeFormatRfc3339 => {
                 let builder = builder.with_timer(UtcTime::rfc_3339());
                 match self.log_format {
-                    FFILogFormat::FFILogFormatText => Box::new(builder.finish()),
-                    FFILogFormat::FFILogFormatJson => Box::new(builder.json().finish()),
-                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact().finish()),
-                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty().finish()),
+                    FFILogFormat::FFILogFormatText => Box::new(builder),
+                    FFILogFormat::FFILogFormatJson => Box::new(builder.json()),
+                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact()),
+                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty()),
                 }
             }
             FFILogTimeFormat::FFILogTimeFormatSystem => {
                 let builder = builder.with_timer(SystemTime::default());
                 match self.log_format {
-                    FFILogFormat::FFILogFormatText => Box::new(builder.finish()),
-                    FFILogFormat::FFILogFormatJson => Box::new(builder.json().finish()),
-                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact().finish()),
-                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty().finish()),
+                    FFILogFormat::FFILogFormatText => Box::new(builder),
+                    FFILogFormat::FFILogFormatJson => Box::new(builder.json()),
+                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact()),
+                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty()),
                 }
             }
             FFILogTimeFormat::FFILogTimeFormatUpTime => {
                 let builder = builder.with_timer(Uptime::default());
                 match self.log_format {
-                    FFILogFormat::FFILogFormatText => Box::new(builder.finish()),
-                    FFILogFormat::FFILogFormatJson => Box::new(builder.json().finish()),
-                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact().finish()),
-                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty().finish()),
+                    FFILogFormat::FFILogFormatText => Box::new(builder),
+                    FFILogFormat::FFILogFormatJson => Box::new(builder.json()),
+                    FFILogFormat::FFILogFormatCompact => Box::new(builder.compact()),
+                    FFILogFormat::FFILogFormatPretty => Box::new(builder.pretty()),
                 }
             }
         }
     }
 }
 
-struct Adapter {
-    handler: FFILogger,
-    inner: Box<dyn tracing::Subscriber + Send + Sync + 'static>,
+/// Where the log events of a logger go
+pub(crate) struct Output {
+    handler: Option<FFILogger>,
+    /// Events wait here instead of calling the handler on the thread which
+    /// logged when set
+    queue:   Option<Arc<LogQueue>>,
+    sinks:   Vec<LogSink>,
 }
 
-impl tracing::Subscriber for Adapter {
-    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        self.inner.enabled(metadata)
-    }
-
-    fn new_span(&self, span: &Attributes<'_>) -> Id {
-        self.inner.new_span(span)
-    }
-
-    fn record(&self, span: &Id, values: &Record<'_>) {
-        self.inner.record(span, values)
-    }
-
-    fn record_follows_from(&self, span: &Id, follows: &Id) {
-        self.inner.record_follows_from(span, follows)
-    }
-
-    fn event(&self, event: &Event<'_>) {
-        self.inner.event(event);
-        let message = LOG_BUFFER.with(|vec| ffi_bytes_array(vec.borrow().as_slice()));
-        self.handler.on_message((*event.metadata().level()).into(), message);
-        LOG_BUFFER.with(|vec| vec.borrow_mut().clear())
-    }
-
-    fn enter(&self, span: &Id) {
-        self.inner.enter(span)
-    }
-
-    fn exit(&self, span: &Id) {
-        self.inner.exit(span)
-    }
-
-    fn clone_span(&self, span: &Id) -> Id {
-        self.inner.clone_span(span)
+impl Output {
+    /// Write `line` to the sinks and hand `entry` to the handler
+    pub(crate) fn deliver(&self, line: &[u8], entry: Option<LogEntry>) {
+        for sink in &self.sinks {
+            sink.write(line);
+        }
+        if let (Some(handler), Some(entry)) = (&self.handler, entry) {
+            handler.deliver(entry);
+        }
     }
+}
 
-    fn try_close(&self, span: Id) -> bool {
-        self.inner.try_close(span)
-    }
+/// Hands the line formatted by the layer below to the sinks and the FFI
+/// callback, through the queue when the logger is buffered
+struct Adapter {
+    output: SharedOutput,
+}
 
-    fn current_span(&self) -> tracing_core::span::Current {
-        self.inner.current_span()
+impl<S> Layer<S> for Adapter
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut line = LOG_BUFFER.with(|vec| std::mem::take(&mut *vec.borrow_mut()));
+        if in_callback() {
+            return;
+        }
+        let output = self.output.read().unwrap_or_else(PoisonError::into_inner);
+        let output = match output.as_ref() {
+            Some(output) => output,
+            None => return,
+        };
+        let level = *event.metadata().level();
+        let entry = match &output.handler {
+            Some(handler) if handler.record_callback.is_some() => {
+                Some(LogEntry::Record(LogRecord::from_event(event, &ctx)))
+            },
+            // the line is only copied when the sinks need it too
+            Some(_) if output.sinks.is_empty() => Some(LogEntry::Line(level, std::mem::take(&mut line))),
+            Some(_) => Some(LogEntry::Line(level, line.clone())),
+            None => None,
+        };
+        match &output.queue {
+            Some(queue) => queue.push(Queued { line, entry }),
+            None => output.deliver(&line, entry),
+        }
     }
 }
 
@@ -37,9 +37,7 @@ impl FFIMessage {
 
         match CStr::from_ptr(ptr).to_str() {
             Ok(s) => {
-                FFIError::from_value(FFIMessage(Message {
-                    message: String::from(s),
-                }))
+                FFIError::from_value(FFIMessage(Message::new(s)))
             },
             Err(error) => FFIError::from(anyhow!(error)),
         }
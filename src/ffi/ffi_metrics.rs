@@ -0,0 +1,96 @@
+use crate::{
+    ffi::FFIArray,
+    metrics::{EventMetrics, MetricsSnapshot},
+    SEventType,
+};
+
+/// Counters of one event type
+#[repr(C)]
+pub struct FFIEventMetrics {
+    pub event_type: SEventType,
+    pub published:  u64,
+    pub delivered:  u64,
+    pub failed:     u64,
+    pub stored:     u64,
+    pub dropped:    u64,
+}
+
+/// Callback calls which took at most `upper_bound_us` microseconds and more
+/// than the bound of the previous bucket, the last bound is `UINT64_MAX`
+#[repr(C)]
+pub struct FFIHistogramBucket {
+    pub upper_bound_us: u64,
+    pub count:          u64,
+}
+
+/// Copy of the metrics of a runner, to free with `destroy_metrics_snapshot`
+#[repr(C)]
+pub struct FFIMetricsSnapshot {
+    /// One entry per event type seen, ordered by type
+    pub events:         FFIArray<FFIEventMetrics>,
+    pub latency:        FFIArray<FFIHistogramBucket>,
+    pub latency_count:  u64,
+    pub latency_sum_ns: u64,
+    pub subscribers:    u64,
+    pub stored:         u64,
+    pub channel_len:    u64,
+    pub channel_peak:   u64,
+    pub lagged:         u64,
+    pub messages_sent:  u64,
+    pub send_errors:    u64,
+}
+
+impl From<MetricsSnapshot<SEventType>> for FFIMetricsSnapshot {
+    fn from(snapshot: MetricsSnapshot<SEventType>) -> Self {
+        let mut events: Vec<(SEventType, EventMetrics)> = snapshot.events.into_iter().collect();
+        events.sort_by_key(|(event_type, _)| u16::from(event_type.clone()));
+        let events: Vec<FFIEventMetrics> = events
+            .into_iter()
+            .map(|(event_type, metrics)| FFIEventMetrics {
+                event_type,
+                published: metrics.published,
+                delivered: metrics.delivered,
+                failed: metrics.failed,
+                stored: metrics.stored,
+                dropped: metrics.dropped,
+            })
+            .collect();
+        let latency: Vec<FFIHistogramBucket> = snapshot
+            .latency
+            .buckets
+            .iter()
+            .map(|(upper_bound_us, count)| FFIHistogramBucket {
+                upper_bound_us: *upper_bound_us,
+                count:          *count,
+            })
+            .collect();
+        Self {
+            events:         FFIArray::from(events),
+            latency:        FFIArray::from(latency),
+            latency_count:  snapshot.latency.count,
+            latency_sum_ns: snapshot.latency.sum.as_nanos() as u64,
+            subscribers:    snapshot.subscribers,
+            stored:         snapshot.stored,
+            channel_len:    snapshot.channel_len,
+            channel_peak:   snapshot.channel_peak,
+            lagged:         snapshot.lagged,
+            messages_sent:  snapshot.messages_sent,
+            send_errors:    snapshot.send_errors,
+        }
+    }
+}
+
+/// Free a snapshot returned by `runner_metrics_snapshot`
+///
+/// # Safety
+/// The snapshot must come from `runner_metrics_snapshot` and not be used
+/// afterwards
+#[no_mangle]
+pub unsafe extern "C" fn destroy_metrics_snapshot(snapshot: *mut FFIMetricsSnapshot) {
+    if snapshot.is_null() {
+        return;
+    }
+    let snapshot = Box::from_raw(snapshot);
+    drop(snapshot.events.into_vec());
+    drop(snapshot.latency.into_vec());
+}
@@ -1,12 +1,36 @@
 use anyhow::anyhow;
+use std::time::Duration;
 
-use crate::ffi::{FFIClientHandle, FFIError, FFIRuntime, FFISafe, FFISubscriber, FFITriple};
-use crate::{ Runner};
+use crate::ffi::{ffi_str, FFIArray, FFIClientHandle, FFIDeadLetter, FFIError, FFIMetricsSnapshot, FFINull, FFIRuntime, FFISafe, FFISubscriber, FFITransportConfig, FFITriple};
+use tokio::sync::broadcast;
+
+#[cfg(feature = "prometheus")]
+use crate::ffi::ffi_bytes_vec;
+use crate::{
+    metrics::Metrics,
+    source::{SourceId, TimerId, TimerSchedule, WatchConfig, WatchId},
+    subscriber::{BreakerPolicy, DeadLetter, RetryPolicy},
+    transport::{ClientEvent, TransportConfig},
+    ClientHandle,
+    Message,
+    Runner,
+    RUNNER_CLIENT_SOURCE,
+    SEnvelope,
+    SEvent,
+    SEventType,
+    SSubscribeHandle,
+};
 
 pub struct FFIRunner(Runner);
 
 impl FFISafe for FFIRunner {}
 
+impl FFIRunner {
+    pub(crate) fn metrics(&self) -> &Metrics<SEventType> {
+        self.0.metrics()
+    }
+}
+
 #[allow(dead_code)]
 impl FFIRunner {
     #[no_mangle]
@@ -18,12 +42,392 @@ impl FFIRunner {
             Err(error) => return error,
         };
 
-        match Runner::new(
+        Self::from_parts(Runner::new(
             rt.runtime().handle(),
-        ) {
+        ))
+    }
+
+    /// Runner whose client talks to the Unix socket at `path`, connection
+    /// changes are delivered as Connected/Disconnected/Reconnecting events
+    ///
+    /// # Safety
+    /// Path should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn create_unix_client(
+        runtime: *mut FFIRuntime,
+        path: *const std::os::raw::c_char,
+    ) -> FFIError<FFITriple<FFIClientHandle, Self, FFISubscriber>> {
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let path = match ffi_str(path) {
+            Ok(path) => path,
+            Err(error) => return error,
+        };
+        Self::from_parts(Runner::unix(rt.runtime().handle(), path, TransportConfig::default()))
+    }
+
+    /// Runner whose client talks to the TCP peer at `addr` (`host:port`),
+    /// connection changes are delivered as Connected/Disconnected/Reconnecting
+    /// events
+    ///
+    /// # Safety
+    /// Addr should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn create_tcp_client(
+        runtime: *mut FFIRuntime,
+        addr: *const std::os::raw::c_char,
+        config: FFITransportConfig,
+    ) -> FFIError<FFITriple<FFIClientHandle, Self, FFISubscriber>> {
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let addr = match ffi_str(addr) {
+            Ok(addr) => addr,
+            Err(error) => return error,
+        };
+        Self::from_parts(Runner::tcp(rt.runtime().handle(), addr, config.into()))
+    }
+
+    /// Runner whose client consumes the WebSocket feed at `url`
+    /// (`ws://host:port/path`, or `wss://` over TLS), the framing of the
+    /// config is ignored and its keepalive is used as the ping interval
+    ///
+    /// # Safety
+    /// Url should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn create_websocket_client(
+        runtime: *mut FFIRuntime,
+        url: *const std::os::raw::c_char,
+        config: FFITransportConfig,
+    ) -> FFIError<FFITriple<FFIClientHandle, Self, FFISubscriber>> {
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let url = match ffi_str(url) {
+            Ok(url) => url,
+            Err(error) => return error,
+        };
+        Self::from_parts(Runner::websocket(rt.runtime().handle(), url, config.into()))
+    }
+
+    /// Runner whose client runs `command` with `args_len` arguments as a child
+    /// process, its stdout and stderr lines and its exit code are delivered as
+    /// events and messages sent through the client are written to its stdin
+    ///
+    /// # Safety
+    /// Command and each of the `args_len` arguments should be NUL terminated
+    /// and respect `CStr::from_ptr` preconditions, args can be null if
+    /// `args_len` is 0
+    #[no_mangle]
+    pub unsafe extern "C" fn create_process_client(
+        runtime: *mut FFIRuntime,
+        command: *const std::os::raw::c_char,
+        args: *const *const std::os::raw::c_char,
+        args_len: usize,
+    ) -> FFIError<FFITriple<FFIClientHandle, Self, FFISubscriber>> {
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let command = match ffi_str(command) {
+            Ok(command) => command,
+            Err(error) => return error,
+        };
+        let arguments = match ffi_args(args, args_len) {
+            Ok(arguments) => arguments,
+            Err(error) => return error,
+        };
+        Self::from_parts(Runner::process(rt.runtime().handle(), command, arguments))
+    }
+
+    /// Merge another client talking to the Unix socket at `path` into the
+    /// runner, its source id is read with `client_handle_source`
+    ///
+    /// # Safety
+    /// Path should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_unix_client(
+        runner: *mut Self,
+        path: *const std::os::raw::c_char,
+    ) -> FFIError<FFIClientHandle> {
+        let path = match ffi_str(path) {
+            Ok(path) => path,
+            Err(error) => return error,
+        };
+        Self::add_client(runner, |runtime| ClientHandle::unix(runtime, path, TransportConfig::default()))
+    }
+
+    /// Merge another client talking to the TCP peer at `addr` into the runner
+    ///
+    /// # Safety
+    /// Addr should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_tcp_client(
+        runner: *mut Self,
+        addr: *const std::os::raw::c_char,
+        config: FFITransportConfig,
+    ) -> FFIError<FFIClientHandle> {
+        let addr = match ffi_str(addr) {
+            Ok(addr) => addr,
+            Err(error) => return error,
+        };
+        Self::add_client(runner, |runtime| ClientHandle::tcp(runtime, addr, config.into()))
+    }
+
+    /// Merge another client consuming the WebSocket feed at `url` into the
+    /// runner
+    ///
+    /// # Safety
+    /// Url should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_websocket_client(
+        runner: *mut Self,
+        url: *const std::os::raw::c_char,
+        config: FFITransportConfig,
+    ) -> FFIError<FFIClientHandle> {
+        let url = match ffi_str(url) {
+            Ok(url) => url,
+            Err(error) => return error,
+        };
+        Self::add_client(runner, |runtime| ClientHandle::websocket(runtime, url, config.into()))
+    }
+
+    /// Merge another client running `command` as a child process into the
+    /// runner
+    ///
+    /// # Safety
+    /// Command and each of the `args_len` arguments should be NUL terminated
+    /// and respect `CStr::from_ptr` preconditions, args can be null if
+    /// `args_len` is 0
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_process_client(
+        runner: *mut Self,
+        command: *const std::os::raw::c_char,
+        args: *const *const std::os::raw::c_char,
+        args_len: usize,
+    ) -> FFIError<FFIClientHandle> {
+        let command = match ffi_str(command) {
+            Ok(command) => command,
+            Err(error) => return error,
+        };
+        let arguments = match ffi_args(args, args_len) {
+            Ok(arguments) => arguments,
+            Err(error) => return error,
+        };
+        Self::add_client(runner, |runtime| ClientHandle::process(runtime, command, arguments))
+    }
+
+    fn add_client<F>(runner: *mut Self, client: F) -> FFIError<FFIClientHandle>
+    where
+        F: FnOnce(
+            &tokio::runtime::Handle,
+        ) -> anyhow::Result<(ClientHandle, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)>,
+    {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        match client(runner.0.runtime()) {
+            Ok((mut handle, client_receiver, events_receiver)) => {
+                handle.set_metrics(runner.metrics().clone());
+                let source = runner.0.add_client(client_receiver, events_receiver);
+                FFIError::from_value(FFIClientHandle {
+                    handle,
+                    source: Some(source),
+                })
+            }
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    /// Emit an event of `event_type` carrying `payload` once after `delay_ms`,
+    /// returns the timer id
+    ///
+    /// # Safety
+    /// Payload should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_timeout(
+        runner: *mut Self,
+        event_type: SEventType,
+        payload: *const std::os::raw::c_char,
+        delay_ms: u64,
+    ) -> FFIError<TimerId> {
+        Self::add_timer(runner, event_type, payload, TimerSchedule::Once(Duration::from_millis(delay_ms)))
+    }
+
+    /// Emit an event of `event_type` carrying `payload` every `interval_ms`,
+    /// returns the timer id
+    ///
+    /// # Safety
+    /// Payload should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_interval(
+        runner: *mut Self,
+        event_type: SEventType,
+        payload: *const std::os::raw::c_char,
+        interval_ms: u64,
+    ) -> FFIError<TimerId> {
+        Self::add_timer(runner, event_type, payload, TimerSchedule::Interval(Duration::from_millis(interval_ms)))
+    }
+
+    /// Emit an event of `event_type` carrying `payload` following the cron
+    /// `expression` (`sec min hour day month weekday [year]` in UTC), returns
+    /// the timer id
+    ///
+    /// # Safety
+    /// Payload and expression should be NUL terminated and respect
+    /// `CStr::from_ptr` preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_add_cron(
+        runner: *mut Self,
+        event_type: SEventType,
+        payload: *const std::os::raw::c_char,
+        expression: *const std::os::raw::c_char,
+    ) -> FFIError<TimerId> {
+        let expression = match ffi_str(expression) {
+            Ok(expression) => expression,
+            Err(error) => return error,
+        };
+        match TimerSchedule::cron(expression) {
+            Ok(schedule) => Self::add_timer(runner, event_type, payload, schedule),
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    /// Stop a timer, fails if it is unknown or already finished
+    #[no_mangle]
+    pub extern "C" fn runner_cancel_timer(runner: *mut Self, timer_id: TimerId) -> FFIError<FFINull> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        if runner.0.cancel_timer(timer_id) {
+            FFIError::from_value(FFINull)
+        } else {
+            FFIError::from(anyhow!("Timer {} is not running", timer_id))
+        }
+    }
+
+    /// Emit FileCreated/FileModified/FileRemoved events for `path`, changes
+    /// are merged until nothing happened for `debounce_ms` but for at most
+    /// `max_wait_ms` after the first of them, returns the watch id
+    ///
+    /// # Safety
+    /// Path should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn runner_watch_path(
+        runner: *mut Self,
+        path: *const std::os::raw::c_char,
+        recursive: bool,
+        debounce_ms: u64,
+        max_wait_ms: u64,
+    ) -> FFIError<WatchId> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        let path = match ffi_str(path) {
+            Ok(path) => path,
+            Err(error) => return error,
+        };
+        let config = WatchConfig {
+            recursive,
+            debounce: Duration::from_millis(debounce_ms),
+            max_wait: Duration::from_millis(max_wait_ms),
+        };
+        match runner.0.watch_path(path, config) {
+            Ok(id) => FFIError::from_value(id),
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    /// Stop watching a path, fails if the watch is unknown
+    #[no_mangle]
+    pub extern "C" fn runner_unwatch_path(runner: *mut Self, watch_id: WatchId) -> FFIError<FFINull> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        if runner.0.unwatch_path(watch_id) {
+            FFIError::from_value(FFINull)
+        } else {
+            FFIError::from(anyhow!("Watch {} is not registered", watch_id))
+        }
+    }
+
+    /// Emit Signal events on SIGINT/SIGTERM/SIGHUP/SIGUSR1, a SIGTERM is
+    /// followed by a Kill event if `kill_on_terminate` is set. Returns the
+    /// source id of the Signal events
+    #[no_mangle]
+    pub extern "C" fn runner_enable_signals(runner: *mut Self, kill_on_terminate: bool) -> FFIError<SourceId> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        match runner.0.enable_signals(kill_on_terminate) {
+            Ok(source) => FFIError::from_value(source),
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    /// Stop emitting Signal events, fails if they were not enabled
+    #[no_mangle]
+    pub extern "C" fn runner_disable_signals(runner: *mut Self) -> FFIError<FFINull> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        if runner.0.disable_signals() {
+            FFIError::from_value(FFINull)
+        } else {
+            FFIError::from(anyhow!("Signals are not enabled"))
+        }
+    }
+
+    unsafe fn add_timer(
+        runner: *mut Self,
+        event_type: SEventType,
+        payload: *const std::os::raw::c_char,
+        schedule: TimerSchedule,
+    ) -> FFIError<TimerId> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        let payload = match ffi_str(payload) {
+            Ok(payload) => payload,
+            Err(error) => return error,
+        };
+        match SEvent::new(event_type, String::from(payload)) {
+            Ok(event) => match runner.0.add_timer(schedule, event) {
+                Ok(id) => FFIError::from_value(id),
+                Err(error) => FFIError::from(error),
+            },
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    fn from_parts(
+        parts: anyhow::Result<(ClientHandle, Runner, SSubscribeHandle)>,
+    ) -> FFIError<FFITriple<FFIClientHandle, Self, FFISubscriber>> {
+        match parts {
             Ok((client_handle, runner, subscribe_handle)) => {
                 let client = FFIClientHandle {
                     handle: client_handle,
+                    source: Some(RUNNER_CLIENT_SOURCE),
                 };
                 let runner = Self(runner);
                 let subscriber = FFISubscriber(subscribe_handle);
@@ -49,6 +453,106 @@ impl FFIRunner {
         }
     }
 
+    /// Counters of the runner, its subscribers and its clients, to free with
+    /// `destroy_metrics_snapshot`
+    #[no_mangle]
+    pub extern "C" fn runner_metrics_snapshot(runner: *mut Self) -> FFIError<FFIMetricsSnapshot> {
+        match Self::safe(runner) {
+            Ok(runner) => FFIError::from_value(FFIMetricsSnapshot::from(runner.metrics().snapshot())),
+            Err(error) => error,
+        }
+    }
+
+    /// Metrics of the runner in the Prometheus text exposition format
+    #[cfg(feature = "prometheus")]
+    #[no_mangle]
+    pub extern "C" fn runner_metrics_prometheus(runner: *mut Self) -> FFIError<FFIArray<std::os::raw::c_uchar>> {
+        match Self::safe(runner) {
+            Ok(runner) => {
+                let text = crate::metrics::render_prometheus(&runner.metrics().snapshot());
+                FFIError::from_value(ffi_bytes_vec(text.into_bytes()))
+            },
+            Err(error) => error,
+        }
+    }
+
+    /// Number of events the subscribers failed to handle
+    #[no_mangle]
+    pub extern "C" fn runner_dead_letters_len(runner: *mut Self) -> FFIError<usize> {
+        match Self::safe(runner) {
+            Ok(runner) => FFIError::from_value(runner.0.dead_letters().len()),
+            Err(error) => error,
+        }
+    }
+
+    /// Copy of the dead letters, oldest first, to free with
+    /// `destroy_dead_letters`
+    #[no_mangle]
+    pub extern "C" fn runner_dead_letters_inspect(runner: *mut Self) -> FFIError<FFIArray<FFIDeadLetter>> {
+        match Self::safe(runner) {
+            Ok(runner) => Self::ffi_dead_letters(runner.0.dead_letters().inspect()),
+            Err(error) => error,
+        }
+    }
+
+    /// Remove up to `max` dead letters, oldest first, to free with
+    /// `destroy_dead_letters`
+    #[no_mangle]
+    pub extern "C" fn runner_dead_letters_drain(runner: *mut Self, max: usize) -> FFIError<FFIArray<FFIDeadLetter>> {
+        match Self::safe(runner) {
+            Ok(runner) => Self::ffi_dead_letters(runner.0.dead_letters().drain(max)),
+            Err(error) => error,
+        }
+    }
+
+    fn ffi_dead_letters(letters: Vec<DeadLetter<SEnvelope>>) -> FFIError<FFIArray<FFIDeadLetter>> {
+        FFIError::from_value(FFIArray::from(letters.into_iter().map(FFIDeadLetter::from).collect::<Vec<_>>()))
+    }
+
+    /// Deliver a failing event up to `max_attempts` times before it becomes a
+    /// dead letter, waiting `backoff_ms` before the first retry and
+    /// multiplying the delay by `multiplier` after each one
+    #[no_mangle]
+    pub extern "C" fn runner_set_retry_policy(
+        runner: *mut Self,
+        max_attempts: u32,
+        backoff_ms: u64,
+        multiplier: f64,
+    ) -> FFIError<FFINull> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        if max_attempts == 0 {
+            return FFIError::from(anyhow!("At least one attempt is needed"));
+        }
+        if !multiplier.is_finite() {
+            return FFIError::from(anyhow!("Invalid backoff multiplier {}", multiplier));
+        }
+        let policy = RetryPolicy::new(max_attempts, Duration::from_millis(backoff_ms)).multiplier(multiplier);
+        runner.0.dead_letters().set_retry_policy(policy);
+        FFIError::from_value(FFINull)
+    }
+
+    /// Suspend a subscriber for `cooldown_ms` once it failed
+    /// `failure_threshold` times in a row, 0 never suspends it, and remove it
+    /// for good when `cooldown_ms` is 0. Applies to the subscribers which
+    /// subscribe afterwards
+    #[no_mangle]
+    pub extern "C" fn runner_set_breaker_policy(
+        runner: *mut Self,
+        failure_threshold: u32,
+        cooldown_ms: u64,
+    ) -> FFIError<FFINull> {
+        let runner = match Self::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        let cooldown = (cooldown_ms > 0).then(|| Duration::from_millis(cooldown_ms));
+        runner.0.set_breaker_policy(BreakerPolicy::new(failure_threshold, cooldown));
+        FFIError::from_value(FFINull)
+    }
+
     #[no_mangle]
     pub extern "C" fn runner_get_runner(
         tuple: *mut FFITriple<FFIClientHandle, Self, FFISubscriber>,
@@ -81,3 +585,18 @@ impl FFIRunner {
         }
     }
 }
+
+/// Read `len` NUL terminated strings from `args`, which may be null when
+/// `len` is 0
+unsafe fn ffi_args<T>(args: *const *const std::os::raw::c_char, len: usize) -> Result<Vec<String>, FFIError<T>> {
+    if len > 0 && args.is_null() {
+        return Err(FFIError::from(anyhow::anyhow!(
+            "Null pointer provided for the arguments"
+        )));
+    }
+    let mut arguments = Vec::with_capacity(len);
+    for i in 0..len {
+        arguments.push(String::from(ffi_str(*args.add(i))?));
+    }
+    Ok(arguments)
+}
@@ -4,9 +4,13 @@ use anyhow::anyhow;
 use tokio::runtime::Runtime;
 
 use crate::ffi::{ffi_error::FFIError, FFISafe};
+#[cfg(feature = "prometheus")]
+use crate::ffi::{ffi_str, FFINull, FFIRunner};
 
 pub struct FFIRuntime {
-    inner: Arc<Runtime>,
+    inner:          Arc<Runtime>,
+    #[cfg(feature = "prometheus")]
+    metrics_server: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl FFISafe for FFIRuntime {
@@ -16,6 +20,8 @@ impl FFIRuntime {
     pub(crate) fn new(inner: Runtime) -> Self {
         Self {
             inner: Arc::new(inner),
+            #[cfg(feature = "prometheus")]
+            metrics_server: None,
         }
     }
 
@@ -46,6 +52,71 @@ impl FFIRuntime {
             Err(error) => FFIError::from(anyhow!(error)),
         }
     }
+
+    /// Serve the metrics of `runner` in the Prometheus text exposition format
+    /// on `GET /metrics` at `addr` such as `127.0.0.1:9100`, returns the
+    /// bound port which is useful with port 0
+    ///
+    /// # Safety
+    /// Addr should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[cfg(feature = "prometheus")]
+    #[no_mangle]
+    pub unsafe extern "C" fn runtime_serve_metrics(
+        runtime: *mut Self,
+        runner: *mut FFIRunner,
+        addr: *const std::os::raw::c_char,
+    ) -> FFIError<u16> {
+        let rt = match Self::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let runner = match FFIRunner::safe(runner) {
+            Ok(runner) => runner,
+            Err(error) => return error,
+        };
+        let addr = match ffi_str(addr) {
+            Ok(addr) => addr,
+            Err(error) => return error,
+        };
+        if rt.metrics_server.is_some() {
+            return FFIError::from(anyhow!("Metrics are already served on this runtime"));
+        }
+        let listener = std::net::TcpListener::bind(addr)
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .and_then(|listener| {
+                let _guard = rt.inner.enter();
+                tokio::net::TcpListener::from_std(listener)
+            });
+        let listener = match listener {
+            Ok(listener) => listener,
+            Err(error) => return FFIError::from(anyhow!("Could not bind the metrics endpoint {}: {}", addr, error)),
+        };
+        let port = match listener.local_addr() {
+            Ok(local) => local.port(),
+            Err(error) => return FFIError::from(anyhow!(error)),
+        };
+        let metrics = runner.metrics().clone();
+        rt.metrics_server = Some(rt.inner.spawn(crate::metrics::serve_prometheus(listener, metrics)));
+        FFIError::from_value(port)
+    }
+
+    /// Stop serving the metrics started by `runtime_serve_metrics`
+    #[cfg(feature = "prometheus")]
+    #[no_mangle]
+    pub extern "C" fn runtime_stop_metrics(runtime: *mut Self) -> FFIError<FFINull> {
+        let rt = match Self::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        match rt.metrics_server.take() {
+            Some(server) => {
+                server.abort();
+                FFIError::from_value(FFINull)
+            },
+            None => FFIError::from(anyhow!("Metrics are not served on this runtime")),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,35 @@
+use crate::source::Signal;
+
+#[repr(u8)]
+pub enum FFISignal {
+    /// SIGINT
+    FFISignalInterrupt = 0,
+    /// SIGTERM
+    FFISignalTerminate = 1,
+    /// SIGHUP
+    FFISignalHangup = 2,
+    /// SIGUSR1
+    FFISignalUser1 = 3,
+}
+
+impl From<Signal> for FFISignal {
+    fn from(signal: Signal) -> Self {
+        match signal {
+            Signal::Interrupt => FFISignal::FFISignalInterrupt,
+            Signal::Terminate => FFISignal::FFISignalTerminate,
+            Signal::Hangup => FFISignal::FFISignalHangup,
+            Signal::User1 => FFISignal::FFISignalUser1,
+        }
+    }
+}
+
+impl From<FFISignal> for Signal {
+    fn from(signal: FFISignal) -> Self {
+        match signal {
+            FFISignal::FFISignalInterrupt => Signal::Interrupt,
+            FFISignal::FFISignalTerminate => Signal::Terminate,
+            FFISignal::FFISignalHangup => Signal::Hangup,
+            FFISignal::FFISignalUser1 => Signal::User1,
+        }
+    }
+}
@@ -1,16 +1,18 @@
 use crate::{ffi::{
     create_heap_pointer,
+    ffi_str,
     FFIArray,
     FFIError,
     FFIRuntime,
     FFISafe,
-}, SEvent, SEventType};
+}, source::SourceId, SEnvelope, SEvent, SEventType, SSubscribeHandle};
 use anyhow::anyhow;
-use crate::ffi::{ffi_bytes_vec, FFINull};
-use crate::subscriber::{SubscribeHandle, SubscriberCallback};
+use std::time::UNIX_EPOCH;
+use crate::ffi::{ffi_bytes_vec, FFINull, FFISignal};
+use crate::subscriber::{SubscriberCallback, Subscription};
 
 pub struct FFISubscriber(
-    pub(in crate::ffi) SubscribeHandle<SEventType, Box<dyn SubscriberCallback<SEvent>>>,
+    pub(in crate::ffi) SSubscribeHandle,
 );
 
 impl FFISafe for FFISubscriber {}
@@ -21,7 +23,91 @@ impl FFISubscriber {
     pub extern "C" fn subscribe(
         handle: *mut Self,
         event_type: SEventType,
-        callback: extern "C" fn(*mut FFISEvent),
+        callback: extern "C" fn(*mut FFISEnvelope),
+        runtime: *mut FFIRuntime,
+    ) -> FFIError<FFINull> {
+        Self::subscribe_to(handle, Subscription::of(event_type), callback, runtime)
+    }
+
+    /// Subscribe to the events accepted by the filter `spec`, `;` separated
+    /// clauses among `type=A,B` (names or numbers), `prefix=text` and
+    /// `regex=expr` which takes the rest of the spec. An empty spec or `*`
+    /// receives every event
+    ///
+    /// # Safety
+    /// Spec should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn subscribe_filter(
+        handle: *mut Self,
+        spec: *const std::os::raw::c_char,
+        callback: extern "C" fn(*mut FFISEnvelope),
+        runtime: *mut FFIRuntime,
+    ) -> FFIError<FFINull> {
+        let spec = match ffi_str(spec) {
+            Ok(spec) => spec,
+            Err(error) => return error,
+        };
+        match spec.parse() {
+            Ok(subscription) => Self::subscribe_to(handle, subscription, callback, runtime),
+            Err(error) => FFIError::from(error),
+        }
+    }
+
+    /// Intercept the events accepted by the filter `spec` (same syntax as
+    /// [`FFISubscriber::subscribe_filter`]) before any subscriber receives
+    /// them. The callback can change the payload with `envelope_set_payload`
+    /// and returns false to drop the event, the envelope is only valid during
+    /// the call
+    ///
+    /// # Safety
+    /// Spec should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn subscribe_interceptor(
+        handle: *mut Self,
+        spec: *const std::os::raw::c_char,
+        callback: extern "C" fn(*mut FFISEnvelope) -> bool,
+        runtime: *mut FFIRuntime,
+    ) -> FFIError<FFINull> {
+        let spec = match ffi_str(spec) {
+            Ok(spec) => spec,
+            Err(error) => return error,
+        };
+        let subscription: Subscription<SEventType, SEnvelope> = match spec.parse() {
+            Ok(subscription) => subscription,
+            Err(error) => return FFIError::from(error),
+        };
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let handle = match FFISubscriber::safe(handle) {
+            Ok(x) => x,
+            Err(error) => return error,
+        };
+        let interceptor = move |envelope: &mut SEnvelope| {
+            let pointer = create_heap_pointer(FFISEnvelope::from(envelope.clone()));
+            let keep = callback(pointer);
+            // the host may have replaced the payload, read the event back
+            let returned = Box::from_raw(pointer);
+            envelope.event = returned.event.into_event();
+            keep
+        };
+        if let Err(e) = rt.runtime().handle().block_on(handle.0.intercept(subscription, interceptor)) {
+            let subscription = e.0.0;
+            return FFIError::from(anyhow!(
+                "Could not create an interceptor for this subscription {:?}",
+                subscription
+            ));
+        }
+        FFIError::from_value(FFINull)
+    }
+
+    fn subscribe_to(
+        handle: *mut Self,
+        subscription: Subscription<SEventType, SEnvelope>,
+        callback: extern "C" fn(*mut FFISEnvelope),
         runtime: *mut FFIRuntime,
     ) -> FFIError<FFINull> {
         let rt = match FFIRuntime::safe(runtime) {
@@ -32,28 +118,117 @@ impl FFISubscriber {
             Ok(x) => x,
             Err(error) => return error,
         };
-        if let Err(e) = rt.runtime().handle().block_on(handle.0.subscribe(
-            event_type,
-            Box::new(move |e| {
-                let event = create_heap_pointer(FFISEvent::from(e));
-                callback(event);
-            }),
-        )) {
-            let typ = e.0.0;
+        let callback: Box<dyn SubscriberCallback<SEnvelope>> = Box::new(move |e| {
+            let event = create_heap_pointer(FFISEnvelope::from(e));
+            callback(event);
+        });
+        if let Err(e) = rt.runtime().handle().block_on(handle.0.subscribe_to(subscription, callback)) {
+            let subscription = e.0.0;
             return FFIError::from(anyhow!(
-                "Could not create a callback for this type {:?}",
-                typ
+                "Could not create a callback for this subscription {:?}",
+                subscription
             ));
         }
         FFIError::from_value(FFINull)
     }
 }
 
+#[repr(C)]
+pub struct FFISEnvelope {
+    /// Unique id of the event within its runner
+    pub id: u64,
+    /// Position of the event among the events of its type, starting at 1
+    pub sequence: u64,
+    /// When the event entered the runner, in nanoseconds since the Unix epoch
+    pub timestamp_ns: u64,
+    /// Source which produced the event
+    pub source: SourceId,
+    pub event: FFISEvent,
+    /// Trace of the event split in its high and low 64 bits, 0 when the event
+    /// is not traced
+    pub trace_id_high: u64,
+    pub trace_id_low: u64,
+    /// Span of the routing hop within the trace, 0 when not traced
+    pub span_id: u64,
+}
+
+/// Replace the payload of an envelope given to an interceptor
+///
+/// # Safety
+/// Envelope should be the one given to the interceptor and payload should be
+/// NUL terminated and respect `CStr::from_ptr` preconditions
+#[no_mangle]
+pub unsafe extern "C" fn envelope_set_payload(
+    envelope: *mut FFISEnvelope,
+    payload: *const std::os::raw::c_char,
+) -> FFIError<FFINull> {
+    if envelope.is_null() {
+        return FFIError::from(anyhow!("Null envelope"));
+    }
+    let payload = match ffi_str(payload) {
+        Ok(payload) => payload,
+        Err(error) => return error,
+    };
+    let array = match &mut (*envelope).event {
+        FFISEvent::Event1(array)
+        | FFISEvent::Event2(array)
+        | FFISEvent::Connected(array)
+        | FFISEvent::Disconnected(array)
+        | FFISEvent::Reconnecting(array)
+        | FFISEvent::ProcessStdout(array)
+        | FFISEvent::ProcessStderr(array)
+        | FFISEvent::FileCreated(array)
+        | FFISEvent::FileModified(array)
+        | FFISEvent::FileRemoved(array)
+        | FFISEvent::SubscriberSuspended(array) => array,
+        FFISEvent::Kill | FFISEvent::ProcessExited(_) | FFISEvent::Signal(_) => {
+            return FFIError::from(anyhow!("This event has no payload"));
+        },
+    };
+    let previous = std::mem::replace(array, ffi_bytes_vec(payload.as_bytes().to_vec()));
+    drop(previous.into_vec());
+    FFIError::from_value(FFINull)
+}
+
+impl From<SEnvelope> for FFISEnvelope {
+    fn from(envelope: SEnvelope) -> Self {
+        let timestamp_ns = envelope
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos() as u64);
+        let (trace_id, span_id) = envelope
+            .trace
+            .as_ref()
+            .map_or((0, 0), |trace| (trace.trace_id(), trace.span_id()));
+        Self {
+            id: envelope.id,
+            sequence: envelope.sequence,
+            timestamp_ns,
+            source: envelope.source,
+            event: FFISEvent::from(envelope.event),
+            trace_id_high: (trace_id >> 64) as u64,
+            trace_id_low: trace_id as u64,
+            span_id,
+        }
+    }
+}
+
 #[repr(C)]
 pub enum FFISEvent {
     Event1(FFIArray<std::os::raw::c_uchar>),
     Event2(FFIArray<std::os::raw::c_uchar>),
     Kill,
+    Connected(FFIArray<std::os::raw::c_uchar>),
+    Disconnected(FFIArray<std::os::raw::c_uchar>),
+    Reconnecting(FFIArray<std::os::raw::c_uchar>),
+    ProcessStdout(FFIArray<std::os::raw::c_uchar>),
+    ProcessStderr(FFIArray<std::os::raw::c_uchar>),
+    ProcessExited(i32),
+    FileCreated(FFIArray<std::os::raw::c_uchar>),
+    FileModified(FFIArray<std::os::raw::c_uchar>),
+    FileRemoved(FFIArray<std::os::raw::c_uchar>),
+    Signal(FFISignal),
+    SubscriberSuspended(FFIArray<std::os::raw::c_uchar>),
 }
 
 impl From<SEvent> for FFISEvent {
@@ -66,6 +241,63 @@ impl From<SEvent> for FFISEvent {
                 Self::Event2(ffi_bytes_vec(s.into_bytes()))
             }
             SEvent::Kill => Self::Kill,
+            SEvent::Connected(s) => {
+                Self::Connected(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::Disconnected(s) => {
+                Self::Disconnected(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::Reconnecting(s) => {
+                Self::Reconnecting(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::ProcessStdout(s) => {
+                Self::ProcessStdout(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::ProcessStderr(s) => {
+                Self::ProcessStderr(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::ProcessExited(code) => Self::ProcessExited(code),
+            SEvent::FileCreated(s) => {
+                Self::FileCreated(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::FileModified(s) => {
+                Self::FileModified(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::FileRemoved(s) => {
+                Self::FileRemoved(ffi_bytes_vec(s.into_bytes()))
+            }
+            SEvent::Signal(signal) => Self::Signal(FFISignal::from(signal)),
+            SEvent::SubscriberSuspended(s) => {
+                Self::SubscriberSuspended(ffi_bytes_vec(s.into_bytes()))
+            }
+        }
+    }
+}
+
+impl FFISEvent {
+    /// Convert back into the event, reclaiming the payload
+    ///
+    /// # Safety
+    /// The payload arrays must have been allocated by this library
+    pub(crate) unsafe fn into_event(self) -> SEvent {
+        let string = |array: FFIArray<std::os::raw::c_uchar>| {
+            String::from_utf8_lossy(&array.into_vec()).into_owned()
+        };
+        match self {
+            Self::Event1(s) => SEvent::Event1(string(s)),
+            Self::Event2(s) => SEvent::Event2(string(s)),
+            Self::Kill => SEvent::Kill,
+            Self::Connected(s) => SEvent::Connected(string(s)),
+            Self::Disconnected(s) => SEvent::Disconnected(string(s)),
+            Self::Reconnecting(s) => SEvent::Reconnecting(string(s)),
+            Self::ProcessStdout(s) => SEvent::ProcessStdout(string(s)),
+            Self::ProcessStderr(s) => SEvent::ProcessStderr(string(s)),
+            Self::ProcessExited(code) => SEvent::ProcessExited(code),
+            Self::FileCreated(s) => SEvent::FileCreated(string(s)),
+            Self::FileModified(s) => SEvent::FileModified(string(s)),
+            Self::FileRemoved(s) => SEvent::FileRemoved(string(s)),
+            Self::Signal(signal) => SEvent::Signal(signal.into()),
+            Self::SubscriberSuspended(s) => SEvent::SubscriberSuspended(string(s)),
         }
     }
 }
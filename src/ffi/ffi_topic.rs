@@ -0,0 +1,143 @@
+use anyhow::anyhow;
+
+use crate::{
+    ffi::{
+        create_heap_pointer,
+        ffi_bytes_vec,
+        ffi_runtime::FFIRuntimeHandle,
+        ffi_str,
+        FFIArray,
+        FFIError,
+        FFINull,
+        FFIRuntime,
+        FFISafe,
+        FFISafeMove,
+    },
+    topic::{TopicEvent, TopicPattern, TopicServer, TopicServerHandle},
+    STopicHandle,
+    Sub,
+};
+
+pub struct FFITopicServer {
+    handle:         STopicHandle,
+    runtime_handle: FFIRuntimeHandle,
+}
+
+impl FFISafe for FFITopicServer {}
+
+impl FFISafeMove for FFITopicServer {}
+
+#[repr(C)]
+pub struct FFITopicEvent {
+    pub topic:   FFIArray<std::os::raw::c_uchar>,
+    pub payload: FFIArray<std::os::raw::c_uchar>,
+}
+
+impl From<TopicEvent> for FFITopicEvent {
+    fn from(event: TopicEvent) -> Self {
+        Self {
+            topic:   ffi_bytes_vec(event.topic.into_bytes()),
+            payload: ffi_bytes_vec(event.payload.into_bytes()),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl FFITopicServer {
+    /// Topic mode event loop, events are published on dot separated topics
+    /// and subscribers use `*` for one segment and a trailing `>` for the
+    /// remaining segments
+    #[no_mangle]
+    pub extern "C" fn create_topic_server(runtime: *mut FFIRuntime) -> FFIError<Self> {
+        let rt = match FFIRuntime::safe(runtime) {
+            Ok(rt) => rt,
+            Err(error) => return error,
+        };
+        let server = TopicServer::<Sub<TopicEvent>>::default();
+        FFIError::from_value(Self {
+            handle:         TopicServerHandle::new(server, rt.runtime().handle()),
+            runtime_handle: rt.handle(),
+        })
+    }
+
+    /// Publish `payload` on `topic` such as `orders.eu.created`
+    ///
+    /// # Safety
+    /// Topic and payload should be NUL terminated and respect
+    /// `CStr::from_ptr` preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn topic_publish(
+        server: *mut Self,
+        topic: *const std::os::raw::c_char,
+        payload: *const std::os::raw::c_char,
+    ) -> FFIError<FFINull> {
+        let server = match Self::safe(server) {
+            Ok(server) => server,
+            Err(error) => return error,
+        };
+        let (topic, payload) = match (ffi_str(topic), ffi_str(payload)) {
+            (Ok(topic), Ok(payload)) => (topic, payload),
+            (Err(error), _) | (_, Err(error)) => return error,
+        };
+        let event = match TopicEvent::new(topic, payload) {
+            Ok(event) => event,
+            Err(error) => return FFIError::from(error),
+        };
+        let runtime = match server.runtime_handle.runtime() {
+            Ok(runtime) => runtime,
+            Err(error) => return error,
+        };
+        match runtime.block_on(server.handle.publish(event)) {
+            Ok(()) => FFIError::from_value(FFINull),
+            Err(err) => FFIError::from(anyhow!("Could not publish {:?}", err.0)),
+        }
+    }
+
+    /// Subscribe to the events whose topic matches `pattern` such as
+    /// `orders.*.created` or `orders.>`
+    ///
+    /// # Safety
+    /// Pattern should be NUL terminated and respect `CStr::from_ptr`
+    /// preconditions
+    #[no_mangle]
+    pub unsafe extern "C" fn topic_subscribe(
+        server: *mut Self,
+        pattern: *const std::os::raw::c_char,
+        callback: extern "C" fn(*mut FFITopicEvent),
+    ) -> FFIError<FFINull> {
+        let server = match Self::safe(server) {
+            Ok(server) => server,
+            Err(error) => return error,
+        };
+        let pattern: TopicPattern = match ffi_str(pattern).map(str::parse) {
+            Ok(Ok(pattern)) => pattern,
+            Ok(Err(error)) => return FFIError::from(error),
+            Err(error) => return error,
+        };
+        let runtime = match server.runtime_handle.runtime() {
+            Ok(runtime) => runtime,
+            Err(error) => return error,
+        };
+        let callback = Box::new(move |e| {
+            let event = create_heap_pointer(FFITopicEvent::from(e));
+            callback(event);
+        });
+        match runtime.block_on(server.handle.subscribe(pattern, callback)) {
+            Ok(()) => FFIError::from_value(FFINull),
+            Err(err) => FFIError::from(anyhow!("Could not subscribe to {}", (err.0).0)),
+        }
+    }
+
+    /// Stop the topic server without waiting for the pending events and free
+    /// it, the subscribers and their callbacks are dropped along with its
+    /// task
+    #[no_mangle]
+    pub extern "C" fn destroy_topic_server(mut server: *mut Self) -> FFIError<FFINull> {
+        let server = match Self::safe_move(&mut server) {
+            Ok(server) => server,
+            Err(error) => return error,
+        };
+        server.handle.stop_handle();
+        FFIError::from_value(FFINull)
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use crate::transport::{Backoff, Framing, TransportConfig};
+
+#[repr(u8)]
+pub enum FFIFraming {
+    /// Messages are prefixed by their length as a big endian u32
+    FFIFramingLengthDelimited = 0,
+    /// Messages are terminated by a newline
+    FFIFramingNewline = 1,
+}
+
+#[repr(C)]
+pub struct FFITransportConfig {
+    /// Message framing on the stream
+    pub(crate) framing: FFIFraming,
+    /// Connection attempt timeout in milliseconds, 0 to wait forever
+    pub(crate) connect_timeout_ms: u64,
+    /// Read inactivity timeout in milliseconds, the peer has to send
+    /// something more often than that, 0 to wait forever
+    pub(crate) read_timeout_ms: u64,
+    /// Write timeout in milliseconds, 0 to wait forever
+    pub(crate) write_timeout_ms: u64,
+    /// TCP keepalive idle time in milliseconds, 0 to disable it
+    pub(crate) keepalive_ms: u64,
+    /// First delay between two connection attempts in milliseconds, at
+    /// least 1
+    pub(crate) backoff_initial_ms: u64,
+    /// Maximum delay between two connection attempts in milliseconds, never
+    /// shorter than the first delay
+    pub(crate) backoff_max_ms: u64,
+}
+
+#[no_mangle]
+pub extern "C" fn create_transport_config(
+    framing: FFIFraming,
+    connect_timeout_ms: u64,
+    read_timeout_ms: u64,
+    write_timeout_ms: u64,
+    keepalive_ms: u64,
+    backoff_initial_ms: u64,
+    backoff_max_ms: u64,
+) -> FFITransportConfig {
+    FFITransportConfig {
+        framing,
+        connect_timeout_ms,
+        read_timeout_ms,
+        write_timeout_ms,
+        keepalive_ms,
+        backoff_initial_ms,
+        backoff_max_ms,
+    }
+}
+
+fn millis(ms: u64) -> Option<Duration> {
+    if ms == 0 {
+        None
+    } else {
+        Some(Duration::from_millis(ms))
+    }
+}
+
+impl From<FFIFraming> for Framing {
+    fn from(framing: FFIFraming) -> Self {
+        match framing {
+            FFIFraming::FFIFramingLengthDelimited => Framing::LengthDelimited,
+            FFIFraming::FFIFramingNewline => Framing::Newline,
+        }
+    }
+}
+
+impl From<FFITransportConfig> for TransportConfig {
+    fn from(config: FFITransportConfig) -> Self {
+        TransportConfig {
+            framing:         config.framing.into(),
+            connect_timeout: millis(config.connect_timeout_ms),
+            read_timeout:    millis(config.read_timeout_ms),
+            write_timeout:   millis(config.write_timeout_ms),
+            keepalive:       millis(config.keepalive_ms),
+            backoff:         Backoff::new(
+                Duration::from_millis(config.backoff_initial_ms),
+                Duration::from_millis(config.backoff_max_ms),
+                2,
+            ),
+        }
+    }
+}
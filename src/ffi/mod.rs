@@ -1,23 +1,41 @@
 pub use ffi_array::FFIArray;
 pub use ffi_client::FFIClient;
+pub use ffi_dead_letter::FFIDeadLetter;
 pub use ffi_error::FFIError;
 pub use ffi_helpers::*;
-pub use ffi_logging::{FFILogFormat, FFILogLevel, FFILogTimeFormat, FFILogger, FFILoggingConfig};
+pub use ffi_log_queue::FFILogOverflow;
+pub use ffi_log_record::{FFILogField, FFILogRecord};
+pub use ffi_log_sink::{FFILogSink, FFILogSinkKind};
+pub use ffi_logging::{FFILogFormat, FFILogLevel, FFILogTimeFormat, FFILogger, FFILoggingConfig, FFILoggingScope};
 pub use ffi_message::FFIMessage;
+pub use ffi_metrics::{FFIEventMetrics, FFIHistogramBucket, FFIMetricsSnapshot};
 pub use ffi_null::FFINull;
 pub use ffi_runtime::FFIRuntime;
-pub use ffi_subscriber::{FFISEvent, FFISubscriber};
+pub use ffi_subscriber::{FFISEnvelope, FFISEvent, FFISubscriber};
 pub use ffi_runner::FFIRunner;
 pub use ffi_client_handle::FFIClientHandle;
+pub use ffi_transport::{FFIFraming, FFITransportConfig};
+pub use ffi_signal::FFISignal;
+pub use ffi_topic::{FFITopicEvent, FFITopicServer};
 
 pub mod ffi_array;
 pub mod ffi_client;
+pub mod ffi_dead_letter;
 pub mod ffi_error;
 pub mod ffi_helpers;
+pub mod ffi_log_fields;
+pub mod ffi_log_host;
+pub mod ffi_log_queue;
+pub mod ffi_log_record;
+pub mod ffi_log_sink;
 pub mod ffi_logging;
 pub mod ffi_message;
+pub mod ffi_metrics;
 pub mod ffi_null;
 pub mod ffi_runtime;
 pub mod ffi_subscriber;
 pub mod ffi_runner;
 pub mod ffi_client_handle;
+pub mod ffi_transport;
+pub mod ffi_signal;
+pub mod ffi_topic;
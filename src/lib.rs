@@ -1,12 +1,36 @@
 pub mod ffi;
+pub mod metrics;
+pub mod source;
 pub mod subscriber;
+pub mod topic;
+pub mod trace;
+pub mod transport;
 mod events;
 
-pub use events::{SConfig, SError, SEvent, SEventType, Sub};
+pub use events::{SConfig, SEnvelope, SError, SEvent, SEventType, SSequencer, Sub};
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, broadcast::error::SendError};
-use tracing::{warn, trace};
-use crate::subscriber::{EventHandle, SubscribeHandle, SubscriberCallback, SubscriberServer, SubscriberServerHandle};
+use std::{future::Future, path::{Path, PathBuf}, sync::{Arc, Mutex, PoisonError}};
+use tokio::sync::{broadcast, broadcast::error::{RecvError, SendError}, mpsc};
+use tracing::{field, info_span, warn, trace, Instrument, Span};
+use crate::source::{
+    Signals, Source, SourceId, SourceItem, Sources, TimerId, TimerSchedule, Timers, WatchConfig, WatchId, Watches,
+};
+use crate::metrics::Metrics;
+use crate::subscriber::{
+    BreakerPolicy, DeadLetters, EventHandle, MiddlewareHandle, SubscribeHandle, SubscriberCallback, SubscriberMiddleware,
+    SubscriberServer, SubscriberServerHandle, EVENT_CHANNEL_SIZE,
+};
+use crate::topic::{TopicEvent, TopicServerHandle};
+use crate::trace::TraceContext;
+use crate::transport::{
+    ClientEvent, ProcessClient, StreamClient, TcpConnector, TransportConfig, UnixConnector, WebSocketClient,
+};
+
+/// Subscription side of the [`Runner`] event loop
+pub type SSubscribeHandle = SubscribeHandle<SEventType, SEnvelope, Box<dyn SubscriberCallback<SEnvelope>>>;
+
+/// Topic mode counterpart of the [`Runner`] event loop
+pub type STopicHandle = TopicServerHandle<Box<dyn SubscriberCallback<TopicEvent>>>;
 
 #[no_mangle]
 pub static FFI_VERSION: [u8; 5] = *b"1.0.0";
@@ -14,6 +38,20 @@ pub static FFI_VERSION: [u8; 5] = *b"1.0.0";
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub message: String,
+    /// Set by [`ClientHandle::send_msg`], it never goes on the wire so only
+    /// the in-process client of [`ClientHandle::new`] hands it back with its
+    /// echo, the messages read by the other clients carry none
+    #[serde(skip)]
+    pub trace: Option<TraceContext>,
+}
+
+impl Message {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Self {
+            message: message.into(),
+            trace: None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -50,83 +88,443 @@ impl AsyncClient {
 #[allow(dead_code)]
 pub struct ClientHandle {
     to_client_tx: broadcast::Sender<Message>,
+    // kept so the receivers only close once the handle is dropped, even for
+    // backends which never produce one of them
+    from_client_tx: broadcast::Sender<Message>,
+    events_tx: broadcast::Sender<ClientEvent>,
     client_join_handle: tokio::task::JoinHandle<()>,
+    metrics: Option<Metrics<SEventType>>,
 }
 
 impl ClientHandle {
     pub fn new(
         runtime: &tokio::runtime::Handle,
     ) -> anyhow::Result<(Self, broadcast::Receiver<Message>)> {
+        let (handle, from_client_rx, _) = Self::spawn(runtime, |from_client_tx, to_client_rx, _| {
+            AsyncClient::new(from_client_tx, to_client_rx).run()
+        });
+        Ok((handle, from_client_rx))
+    }
+
+    /// Client talking framed messages over the Unix socket at `path`, the
+    /// returned [`ClientEvent`] receiver follows the connection state
+    pub fn unix<P: Into<PathBuf>>(
+        runtime: &tokio::runtime::Handle,
+        path: P,
+        config: TransportConfig,
+    ) -> anyhow::Result<(Self, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)> {
+        let connector = UnixConnector::new(path);
+        Ok(Self::spawn(runtime, move |from_client_tx, to_client_rx, events_tx| {
+            StreamClient::new(connector, config, from_client_tx, to_client_rx, events_tx).run()
+        }))
+    }
+
+    /// Client talking framed messages to the TCP peer at `addr` (`host:port`),
+    /// the returned [`ClientEvent`] receiver follows the connection state
+    pub fn tcp<A: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        addr: A,
+        config: TransportConfig,
+    ) -> anyhow::Result<(Self, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)> {
+        let connector = TcpConnector::new(addr, config.keepalive);
+        Ok(Self::spawn(runtime, move |from_client_tx, to_client_rx, events_tx| {
+            StreamClient::new(connector, config, from_client_tx, to_client_rx, events_tx).run()
+        }))
+    }
+
+    /// Client consuming the WebSocket feed at `url` (`ws://host:port/path`,
+    /// or `wss://` over TLS), the returned [`ClientEvent`] receiver follows
+    /// the connection state
+    pub fn websocket<U: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        url: U,
+        config: TransportConfig,
+    ) -> anyhow::Result<(Self, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)> {
+        let url = url.into();
+        Ok(Self::spawn(runtime, move |from_client_tx, to_client_rx, events_tx| {
+            WebSocketClient::new(url, config, from_client_tx, to_client_rx, events_tx).run()
+        }))
+    }
+
+    /// Client running `command` as a child process, its output lines and exit
+    /// code are delivered on the returned [`ClientEvent`] receiver and every
+    /// message sent is written as a line to its stdin
+    pub fn process<C: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        command: C,
+        args: Vec<String>,
+    ) -> anyhow::Result<(Self, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)> {
+        let command = command.into();
+        Ok(Self::spawn(runtime, move |_, to_client_rx, events_tx| {
+            ProcessClient::new(command, args, to_client_rx, events_tx).run()
+        }))
+    }
+
+    fn spawn<F, Fut>(
+        runtime: &tokio::runtime::Handle,
+        client: F,
+    ) -> (Self, broadcast::Receiver<Message>, broadcast::Receiver<ClientEvent>)
+    where
+        F: FnOnce(broadcast::Sender<Message>, broadcast::Receiver<Message>, broadcast::Sender<ClientEvent>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
         let (from_client_tx, from_client_rx) = broadcast::channel(1024);
         let (to_client_tx, to_client_rx) = broadcast::channel(1024);
-        let async_client = AsyncClient::new(from_client_tx, to_client_rx);
-        let client_join_handle = runtime.spawn(async move { async_client.run().await });
-        Ok((
+        let (events_tx, events_rx) = broadcast::channel(1024);
+        let client_join_handle = runtime.spawn(client(from_client_tx.clone(), to_client_rx, events_tx.clone()));
+        (
             Self {
                 to_client_tx,
+                from_client_tx,
+                events_tx,
                 client_join_handle,
+                metrics: None,
             },
             from_client_rx,
-        ))
+            events_rx,
+        )
     }
 
-    pub fn send_msg(&mut self, msg: Message) -> Result<usize, SendError<Message>> {
-        self.to_client_tx.send(msg)
+    /// Subscribe to the connection lifecycle of the client backend
+    pub fn events(&self) -> broadcast::Receiver<ClientEvent> {
+        self.events_tx.subscribe()
+    }
+
+    /// Count the messages sent through this handle in `metrics`
+    pub fn set_metrics(&mut self, metrics: Metrics<SEventType>) {
+        self.metrics = Some(metrics);
+    }
+
+    /// Send `msg` to the client within a new trace, see [`TraceContext`].
+    /// The trace stays in the process: the replies read from a socket,
+    /// WebSocket or child process are not correlated with it
+    pub fn send_msg(&mut self, mut msg: Message) -> Result<usize, SendError<Message>> {
+        let span = info_span!("send_msg", trace_id = field::Empty, span_id = field::Empty);
+        msg.trace = Some(TraceContext::new(span));
+        let result = self.to_client_tx.send(msg);
+        if let Some(metrics) = &self.metrics {
+            match result {
+                Ok(_) => metrics.messages_sent().inc(),
+                Err(_) => metrics.send_errors().inc(),
+            }
+        }
+        result
     }
 }
 
+/// Source id of the client a [`Runner`] is created with
+pub const RUNNER_CLIENT_SOURCE: SourceId = 0;
+
+/// Source id of the events raised by the subscriber server itself, such as
+/// [`SEvent::SubscriberSuspended`]
+pub const SUBSCRIBER_SERVER_SOURCE: SourceId = SourceId::MAX;
+
 #[allow(dead_code)]
 pub struct Runner {
     subscriber_join_handle: tokio::task::JoinHandle<()>,
     runner_join_handle: tokio::task::JoinHandle<()>,
+    runtime: tokio::runtime::Handle,
+    sources_tx: mpsc::UnboundedSender<(SourceId, Source<SEvent>)>,
+    next_source: SourceId,
+    timers: Timers,
+    watches: Watches,
+    signals: Signals,
+    dead_letters: DeadLetters<SEnvelope>,
+    middlewares: MiddlewareHandle<SEnvelope>,
+    breaker_policy: Arc<Mutex<BreakerPolicy>>,
+    metrics: Metrics<SEventType>,
 }
 
 impl Runner {
     pub fn new(
         runtime: &tokio::runtime::Handle,
-    ) -> anyhow::Result<(ClientHandle, Self, SubscribeHandle<SEventType, Box<dyn SubscriberCallback<SEvent>>>)> {
+    ) -> anyhow::Result<(ClientHandle, Self, SSubscribeHandle)> {
         let (client_handle, client_receiver) = ClientHandle::new(runtime)?;
-        let sub_server = SubscriberServer::<_, _, _, Sub<SEvent>>::new(SConfig::default());
+        let events_receiver = client_handle.events();
+        Ok(Self::with_client(runtime, client_handle, client_receiver, events_receiver))
+    }
+
+    /// Same as [`Runner::new`] but the client is connected to the Unix socket
+    /// at `path`
+    pub fn unix<P: Into<PathBuf>>(
+        runtime: &tokio::runtime::Handle,
+        path: P,
+        config: TransportConfig,
+    ) -> anyhow::Result<(ClientHandle, Self, SSubscribeHandle)> {
+        let (client_handle, client_receiver, events_receiver) = ClientHandle::unix(runtime, path, config)?;
+        Ok(Self::with_client(runtime, client_handle, client_receiver, events_receiver))
+    }
+
+    /// Same as [`Runner::new`] but the client is connected to the TCP peer at
+    /// `addr`
+    pub fn tcp<A: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        addr: A,
+        config: TransportConfig,
+    ) -> anyhow::Result<(ClientHandle, Self, SSubscribeHandle)> {
+        let (client_handle, client_receiver, events_receiver) = ClientHandle::tcp(runtime, addr, config)?;
+        Ok(Self::with_client(runtime, client_handle, client_receiver, events_receiver))
+    }
+
+    /// Same as [`Runner::new`] but the client consumes the WebSocket feed at
+    /// `url`
+    pub fn websocket<U: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        url: U,
+        config: TransportConfig,
+    ) -> anyhow::Result<(ClientHandle, Self, SSubscribeHandle)> {
+        let (client_handle, client_receiver, events_receiver) = ClientHandle::websocket(runtime, url, config)?;
+        Ok(Self::with_client(runtime, client_handle, client_receiver, events_receiver))
+    }
+
+    /// Same as [`Runner::new`] but the client runs `command` as a child
+    /// process
+    pub fn process<C: Into<String>>(
+        runtime: &tokio::runtime::Handle,
+        command: C,
+        args: Vec<String>,
+    ) -> anyhow::Result<(ClientHandle, Self, SSubscribeHandle)> {
+        let (client_handle, client_receiver, events_receiver) = ClientHandle::process(runtime, command, args)?;
+        Ok(Self::with_client(runtime, client_handle, client_receiver, events_receiver))
+    }
+
+    fn with_client(
+        runtime: &tokio::runtime::Handle,
+        mut client_handle: ClientHandle,
+        client_receiver: broadcast::Receiver<Message>,
+        events_receiver: broadcast::Receiver<ClientEvent>,
+    ) -> (ClientHandle, Self, SSubscribeHandle) {
+        let config = SConfig::default();
+        let breaker_policy = config.breaker_policy.clone();
+        let sub_server = SubscriberServer::<_, _, _, Sub<SEnvelope>>::new(config);
+        let dead_letters = sub_server.dead_letters();
+        let middlewares = sub_server.middleware_handle();
+        let metrics = sub_server.metrics();
+        client_handle.set_metrics(metrics.clone());
         let sub_handler = SubscriberServerHandle::new(sub_server, runtime);
         let (send_handle, subscribe_handle, subscriber_join_handle) = sub_handler.split();
-        let runner_join_handle = runtime.spawn(Self::run(client_receiver, send_handle));
-        Ok((client_handle, Runner {
+        let (sources_tx, sources_rx) = mpsc::unbounded_channel();
+        let runner_join_handle = runtime.spawn(Self::run(sources_rx, send_handle, metrics.clone()));
+        let mut runner = Runner {
             subscriber_join_handle,
             runner_join_handle,
-        }, subscribe_handle))
+            runtime: runtime.clone(),
+            sources_tx,
+            next_source: RUNNER_CLIENT_SOURCE,
+            timers: Timers::default(),
+            watches: Watches::default(),
+            signals: Signals::default(),
+            dead_letters,
+            middlewares,
+            breaker_policy,
+            metrics,
+        };
+        runner.add_client(client_receiver, events_receiver);
+        (client_handle, runner, subscribe_handle)
+    }
+
+    pub(crate) fn runtime(&self) -> &tokio::runtime::Handle {
+        &self.runtime
+    }
+
+    /// Events the subscribers failed to handle, along with the retry policy
+    /// applied before they end up there
+    pub fn dead_letters(&self) -> &DeadLetters<SEnvelope> {
+        &self.dead_letters
+    }
+
+    /// When a subscriber failing in a row is suspended or removed, it applies
+    /// to the subscribers which subscribe afterwards. Defaults to
+    /// [`BreakerPolicy::default`]
+    pub fn set_breaker_policy(&self, policy: BreakerPolicy) {
+        *self.breaker_policy.lock().unwrap_or_else(PoisonError::into_inner) = policy;
+    }
+
+    /// Wrap the dispatch of the events which reach the subscriber server from
+    /// now on, after the middlewares already added
+    pub fn add_middleware<M>(&self, middleware: M) -> anyhow::Result<()>
+    where
+        M: SubscriberMiddleware<SEnvelope> + 'static,
+    {
+        self.middlewares.add(middleware)
+    }
+
+    /// Counters of the runner, its subscribers and its client
+    pub fn metrics(&self) -> &Metrics<SEventType> {
+        &self.metrics
+    }
+
+    /// Merge the messages and lifecycle events of another client into the
+    /// runner, returns the source id carried by its events. Only the client
+    /// the runner was created with shuts it down when it goes away
+    pub fn add_client(
+        &mut self,
+        client_receiver: broadcast::Receiver<Message>,
+        events_receiver: broadcast::Receiver<ClientEvent>,
+    ) -> SourceId {
+        self.add_source(Source::Client {
+            messages: client_receiver,
+            events: events_receiver,
+        })
+    }
+
+    /// Merge the events received on `receiver` into the runner until all its
+    /// senders are dropped, returns the source id carried by these events
+    pub fn add_events(&mut self, receiver: mpsc::Receiver<SEvent>) -> SourceId {
+        self.add_source(Source::Events(receiver))
+    }
+
+    fn add_source(&mut self, source: Source<SEvent>) -> SourceId {
+        let id = self.next_source;
+        self.next_source += 1;
+        if self.sources_tx.send((id, source)).is_err() {
+            warn!("Runner loop is gone, source {} is ignored", id);
+        }
+        id
+    }
+
+    /// New events source along with the handle to send its events
+    fn event_source(&mut self) -> (SourceId, EventHandle<SEvent>) {
+        let (send_event, receiver) = mpsc::channel(1024);
+        (self.add_events(receiver), EventHandle { send_event })
+    }
+
+    /// Emit a clone of `event` to the subscribers following `schedule`, the
+    /// timer lives until cancelled or until the runner is dropped. The timer
+    /// id is also the source id of its events
+    ///
+    /// # Errors
+    /// If the schedule can not be run, see [`TimerSchedule::check`]
+    pub fn add_timer(&mut self, schedule: TimerSchedule, event: SEvent) -> anyhow::Result<TimerId> {
+        schedule.check()?;
+        let (id, send_handle) = self.event_source();
+        self.timers.add(id, &self.runtime, schedule, send_handle, event)?;
+        Ok(id)
+    }
+
+    /// Stop a timer, returns false if it is unknown or already finished
+    pub fn cancel_timer(&mut self, id: TimerId) -> bool {
+        self.timers.cancel(id)
+    }
+
+    /// Emit FileCreated/FileModified/FileRemoved events with the changed path
+    /// as payload for `path`, the watch lives until removed or until the
+    /// runner is dropped. The watch id is also the source id of its events
+    ///
+    /// # Errors
+    /// If the path can not be watched
+    pub fn watch_path<P: AsRef<Path>>(&mut self, path: P, config: WatchConfig) -> anyhow::Result<WatchId> {
+        // the source id is only taken once the path is watched
+        let (send_event, receiver) = mpsc::channel(1024);
+        let id = self.next_source;
+        self.watches.add(id, &self.runtime, path.as_ref(), config, EventHandle { send_event })?;
+        Ok(self.add_events(receiver))
+    }
+
+    /// Stop watching a path, returns false if the watch is unknown
+    pub fn unwatch_path(&mut self, id: WatchId) -> bool {
+        self.watches.remove(id)
+    }
+
+    /// Emit a Signal event on SIGINT/SIGTERM/SIGHUP/SIGUSR1, with
+    /// `kill_on_terminate` a SIGTERM is followed by [`SEvent::Kill`] to shut
+    /// the subscribers down. The signals are no longer handled by their
+    /// default behaviour afterwards, even once disabled. Returns the source id
+    /// of the Signal events
+    ///
+    /// # Errors
+    /// If the signal handlers can not be registered
+    pub fn enable_signals(&mut self, kill_on_terminate: bool) -> anyhow::Result<SourceId> {
+        let on_terminate = kill_on_terminate.then_some(SEvent::Kill);
+        let (id, send_handle) = self.event_source();
+        self.signals.enable(&self.runtime, send_handle, on_terminate)?;
+        Ok(id)
+    }
+
+    /// Stop emitting Signal events, returns false if they were not enabled
+    pub fn disable_signals(&mut self) -> bool {
+        self.signals.disable()
     }
 
-    #[allow(unused_assignments)]
-    pub async fn run(mut client_receiver: broadcast::Receiver<Message>, send_handle: EventHandle<SEvent>) {
+    /// Forward what every source produces to the subscribers, new sources are
+    /// received on `new_sources`. Stops once the runner is dropped and all
+    /// its sources are closed
+    pub async fn run(
+        mut new_sources: mpsc::UnboundedReceiver<(SourceId, Source<SEvent>)>,
+        send_handle: EventHandle<SEnvelope>,
+        metrics: Metrics<SEventType>,
+    ) {
+        let mut sources = Sources::default();
+        let mut sequencer = SSequencer::default();
         loop {
-            // you can handle more than one receiver by using this pattern of optional and select! short circuit
-            let mut msg_client = None;
             tokio::select! {
-                msg =client_receiver.recv() =>{
-                    trace!("Received client message");
-                    msg_client=Some(msg);
+                Some((id, source)) = new_sources.recv() => {
+                    trace!("Adding source {}", id);
+                    sources.push(id, source);
+                }
+                Some((id, item)) = sources.next() => {
+                    Self::forward(&send_handle, &metrics, &mut sequencer, id, item).await;
+                }
+                else => {
+                    break;
                 }
             }
-            if let Some(msg) = msg_client {
-                match msg {
-                    Ok(msg) => {
-                        let err = if msg.message.starts_with("test") {
-                            send_handle.send(SEvent::Event1(msg.message)).await
-                        } else {
-                            send_handle.send(SEvent::Event2(msg.message)).await
-                        };
-                        if let Err(err) = err {
-                            warn!("Could not send normal Event {:?}",err);
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Error wile receiving message {}",e);
-                        if let Err(err) = send_handle.send(SEvent::Kill).await {
-                            warn!("Could not send kill Event {:?}",err);
-                        }
-                    }
+        }
+    }
+
+    async fn forward(
+        send_handle: &EventHandle<SEnvelope>,
+        metrics: &Metrics<SEventType>,
+        sequencer: &mut SSequencer,
+        source: SourceId,
+        item: SourceItem<SEvent>,
+    ) {
+        if let SourceItem::Message(Err(RecvError::Lagged(missed))) = &item {
+            metrics.lagged().add(*missed);
+        }
+        let mut parent = None;
+        let event = match item {
+            SourceItem::Message(Ok(msg)) => {
+                trace!("Received client message");
+                parent = msg.trace;
+                if msg.message.starts_with("test") {
+                    SEvent::Event1(msg.message)
+                } else {
+                    SEvent::Event2(msg.message)
                 }
             }
+            SourceItem::Message(Err(e)) if source != RUNNER_CLIENT_SOURCE => {
+                warn!("Error wile receiving message from source {} {}", source, e);
+                return;
+            }
+            SourceItem::Message(Err(e)) => {
+                warn!("Error wile receiving message {}", e);
+                SEvent::Kill
+            }
+            SourceItem::Client(event) => {
+                trace!("Received client event");
+                SEvent::from(event)
+            }
+            SourceItem::Event(event) => event,
+        };
+        let mut envelope = sequencer.envelope(source, event);
+        envelope.trace = parent.map(|parent| {
+            let span = info_span!(
+                parent: parent.span(),
+                "route",
+                source,
+                event_id = envelope.id,
+                trace_id = field::Empty,
+                span_id = field::Empty
+            );
+            parent.child(span)
+        });
+        let span = envelope.trace.as_ref().map_or_else(Span::none, |trace| trace.span().clone());
+        // sampled before the send as well, it only completes once there is room
+        metrics.channel().set(EVENT_CHANNEL_SIZE.saturating_sub(send_handle.capacity()) as u64);
+        if let Err(err) = send_handle.send(envelope).instrument(span).await {
+            warn!("Could not send Event {:?}", err);
         }
+        metrics.channel().set(EVENT_CHANNEL_SIZE.saturating_sub(send_handle.capacity()) as u64);
     }
-}
\ No newline at end of file
+}
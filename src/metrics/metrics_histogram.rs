@@ -0,0 +1,92 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Upper bounds of the [`Histogram`] buckets in microseconds, the last
+/// bucket holds everything slower
+pub const LATENCY_BUCKETS_US: [u64; 12] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000, 100_000, 1_000_000,
+];
+
+/// Latency histogram with fixed buckets, clones share the same values
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    inner: Arc<HistogramInner>,
+}
+
+#[derive(Debug)]
+struct HistogramInner {
+    // one more than the bounds for the overflow bucket
+    buckets:   Vec<AtomicU64>,
+    count:     AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+/// Copy of a [`Histogram`] at some point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    /// Upper bound in microseconds of each bucket, `u64::MAX` for the last
+    /// one, along with the number of values which fell in it
+    pub buckets: Vec<(u64, u64)>,
+    pub count:   u64,
+    pub sum:     Duration,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(HistogramInner {
+                buckets:   (0..=LATENCY_BUCKETS_US.len()).map(|_| AtomicU64::new(0)).collect(),
+                count:     AtomicU64::new(0),
+                sum_nanos: AtomicU64::new(0),
+            }),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&self, elapsed: Duration) {
+        let micros = elapsed.as_micros();
+        let bucket = LATENCY_BUCKETS_US.partition_point(|bound| u128::from(*bound) < micros);
+        self.inner.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.inner.count.fetch_add(1, Ordering::Relaxed);
+        self.inner.sum_nanos.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let bounds = LATENCY_BUCKETS_US.iter().copied().chain(std::iter::once(u64::MAX));
+        HistogramSnapshot {
+            buckets: bounds
+                .zip(&self.inner.buckets)
+                .map(|(bound, count)| (bound, count.load(Ordering::Relaxed)))
+                .collect(),
+            count:   self.inner.count.load(Ordering::Relaxed),
+            sum:     Duration::from_nanos(self.inner.sum_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl HistogramSnapshot {
+    /// Upper bound of the bucket holding the `quantile` (between 0 and 1) of
+    /// the values, None before the first value
+    #[must_use]
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((self.count as f64) * quantile.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bound, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros(*bound));
+            }
+        }
+        None
+    }
+}
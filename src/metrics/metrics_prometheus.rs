@@ -0,0 +1,283 @@
+use std::{
+    fmt::{Display, Write},
+    hash::Hash,
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, warn};
+
+use crate::metrics::{EventMetrics, Metrics, MetricsSnapshot, SubscriberMetrics};
+
+/// Prefix of every exposed metric
+pub const PROMETHEUS_NAMESPACE: &str = "rust_py";
+
+/// Content type of the text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Name, help and value of a labelled counter
+type LabelledCounter<Metrics> = (&'static str, &'static str, fn(&Metrics) -> u64);
+
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Render `snapshot` in the Prometheus text exposition format, event
+/// counters are labelled with `event_type` and subscriber counters with
+/// `subscriber`
+#[must_use]
+pub fn render_prometheus<Type: Display + Eq + Hash>(snapshot: &MetricsSnapshot<Type>) -> String {
+    let mut out = Exposition::default();
+
+    let mut events: Vec<(String, _)> = snapshot
+        .events
+        .iter()
+        .map(|(event_type, metrics)| (event_type.to_string(), metrics))
+        .collect();
+    events.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let event_counters: [LabelledCounter<EventMetrics>; 5] = [
+        (
+            "events_published_total",
+            "Events which reached the subscriber server",
+            |m| m.published,
+        ),
+        ("events_delivered_total", "Successful callback calls", |m| {
+            m.delivered
+        }),
+        ("events_failed_total", "Failed callback calls", |m| m.failed),
+        (
+            "events_stored_total",
+            "Events kept until a subscriber shows up",
+            |m| m.stored,
+        ),
+        (
+            "events_dropped_total",
+            "Events vetoed or evicted from the store",
+            |m| m.dropped,
+        ),
+    ];
+    for (name, help, value) in event_counters {
+        out.header(name, "counter", help);
+        for (event_type, metrics) in &events {
+            out.sample(name, &[("event_type", event_type)], value(metrics));
+        }
+    }
+
+    let mut subscribers: Vec<_> = snapshot.by_subscriber.iter().collect();
+    subscribers.sort_by_key(|(subscriber, _)| **subscriber);
+    let subscriber_counters: [LabelledCounter<SubscriberMetrics>; 3] = [
+        (
+            "subscriber_delivered_total",
+            "Successful callback calls of a subscriber",
+            |m| m.delivered,
+        ),
+        (
+            "subscriber_failed_total",
+            "Failed callback calls of a subscriber",
+            |m| m.failed,
+        ),
+        (
+            "subscriber_suspensions_total",
+            "Times the circuit breaker of a subscriber tripped",
+            |m| m.suspensions,
+        ),
+    ];
+    for (name, help, value) in subscriber_counters {
+        out.header(name, "counter", help);
+        for (subscriber, metrics) in &subscribers {
+            out.sample(
+                name,
+                &[("subscriber", &subscriber.to_string())],
+                value(metrics),
+            );
+        }
+    }
+
+    let name = "callback_duration_seconds";
+    out.header(name, "histogram", "Duration of the callback calls");
+    let mut cumulative = 0;
+    for (bound, count) in &snapshot.latency.buckets {
+        cumulative += count;
+        let le = if *bound == u64::MAX {
+            String::from("+Inf")
+        } else {
+            (*bound as f64 / 1e6).to_string()
+        };
+        out.sample(
+            "callback_duration_seconds_bucket",
+            &[("le", &le)],
+            cumulative,
+        );
+    }
+    out.sample_f64(
+        "callback_duration_seconds_sum",
+        snapshot.latency.sum.as_secs_f64(),
+    );
+    out.sample(
+        "callback_duration_seconds_count",
+        &[],
+        snapshot.latency.count,
+    );
+
+    let gauges = [
+        ("subscribers", "Active subscribers", snapshot.subscribers),
+        (
+            "stored_events",
+            "Events currently waiting in the store",
+            snapshot.stored,
+        ),
+        (
+            "channel_events",
+            "Events waiting in the channel to the subscriber server",
+            snapshot.channel_len,
+        ),
+        (
+            "channel_events_peak",
+            "Highest number of events waiting in the channel",
+            snapshot.channel_peak,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        out.header(name, "gauge", help);
+        out.sample(name, &[], value);
+    }
+
+    let counters = [
+        (
+            "lagged_messages_total",
+            "Messages the runner missed because it was too slow",
+            snapshot.lagged,
+        ),
+        (
+            "messages_sent_total",
+            "Messages sent through the client handle",
+            snapshot.messages_sent,
+        ),
+        (
+            "send_errors_total",
+            "Messages sent through the client handle which found no receiver",
+            snapshot.send_errors,
+        ),
+    ];
+    for (name, help, value) in counters {
+        out.header(name, "counter", help);
+        out.sample(name, &[], value);
+    }
+
+    out.text
+}
+
+/// Answer `GET /metrics` on `listener` with the current values of
+/// `metrics` until the task is aborted
+pub async fn serve_prometheus<Type>(listener: TcpListener, metrics: Metrics<Type>)
+where
+    Type: Display + Eq + Hash + Clone + Send + 'static,
+{
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                debug!("Metrics scraped by {}", peer);
+                let metrics = metrics.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = respond(stream, &metrics).await {
+                        debug!(
+                            "Could not answer the metrics request of {} {:?}",
+                            peer, error
+                        );
+                    }
+                });
+            },
+            Err(error) => warn!("Could not accept a metrics connection {:?}", error),
+        }
+    }
+}
+
+async fn respond<Type>(mut stream: TcpStream, metrics: &Metrics<Type>) -> std::io::Result<()>
+where
+    Type: Display + Eq + Hash + Clone,
+{
+    let mut request = Vec::with_capacity(1024);
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        if request.len() >= MAX_REQUEST_SIZE {
+            break;
+        }
+        let read = tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut buffer)).await.map_err(
+            |_| std::io::Error::new(std::io::ErrorKind::TimedOut, "Request timed out"),
+        )??;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+    let response = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics" | "/")) => {
+            let body = render_prometheus(&metrics.snapshot());
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: \
+                 close\r\n\r\n{}",
+                PROMETHEUS_CONTENT_TYPE,
+                body.len(),
+                body
+            )
+        },
+        (Some("GET"), _) => {
+            String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        },
+        _ => String::from(
+            "HTTP/1.1 405 Method Not Allowed\r\nAllow: GET\r\nContent-Length: 0\r\nConnection: \
+             close\r\n\r\n",
+        ),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[derive(Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(
+            self.text,
+            "# HELP {}_{} {}",
+            PROMETHEUS_NAMESPACE, name, help
+        );
+        let _ = writeln!(
+            self.text,
+            "# TYPE {}_{} {}",
+            PROMETHEUS_NAMESPACE, name, kind
+        );
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let _ = write!(self.text, "{}_{}", PROMETHEUS_NAMESPACE, name);
+        if !labels.is_empty() {
+            self.text.push('{');
+            for (index, (label, value)) in labels.iter().enumerate() {
+                if index > 0 {
+                    self.text.push(',');
+                }
+                let _ = write!(self.text, "{}=\"{}\"", label, escape(value));
+            }
+            self.text.push('}');
+        }
+        let _ = writeln!(self.text, " {}", value);
+    }
+
+    fn sample_f64(&mut self, name: &str, value: f64) {
+        let _ = writeln!(self.text, "{}_{} {}", PROMETHEUS_NAMESPACE, name, value);
+    }
+}
+
+/// Label values escape backslashes, double quotes and line feeds
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
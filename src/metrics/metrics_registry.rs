@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use crate::{
+    metrics::{Counter, Gauge, Histogram, HistogramSnapshot},
+    subscriber::SubscriberId,
+};
+
+/// Counters of the events of one type going through a
+/// [`crate::subscriber::SubscriberServer`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventMetrics {
+    /// Events which reached the server
+    pub published: u64,
+    /// Successful callback calls
+    pub delivered: u64,
+    /// Failed callback calls
+    pub failed:    u64,
+    /// Events kept until a subscriber shows up
+    pub stored:    u64,
+    /// Events vetoed by a middleware or an interceptor, or evicted from the
+    /// store
+    pub dropped:   u64,
+}
+
+/// Counters of one subscriber of a [`crate::subscriber::SubscriberServer`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriberMetrics {
+    /// Successful callback calls
+    pub delivered:   u64,
+    /// Failed callback calls
+    pub failed:      u64,
+    /// Times its circuit breaker tripped
+    pub suspensions: u64,
+}
+
+/// Metrics shared by a [`crate::Runner`], its subscriber server and its
+/// client, clones update the same values
+#[derive(Debug)]
+pub struct Metrics<Type> {
+    events:        Arc<Mutex<HashMap<Type, EventMetrics>>>,
+    by_subscriber: Arc<Mutex<HashMap<SubscriberId, SubscriberMetrics>>>,
+    latency:       Histogram,
+    subscribers:   Gauge,
+    stored:        Gauge,
+    channel:       Gauge,
+    lagged:        Counter,
+    messages_sent: Counter,
+    send_errors:   Counter,
+}
+
+/// Copy of the [`Metrics`] at some point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsSnapshot<Type: Eq + Hash> {
+    pub events:        HashMap<Type, EventMetrics>,
+    pub by_subscriber: HashMap<SubscriberId, SubscriberMetrics>,
+    /// Duration of the callback calls
+    pub latency:       HistogramSnapshot,
+    /// Active subscribers
+    pub subscribers:   u64,
+    /// Events currently waiting in the store
+    pub stored:        u64,
+    /// Events waiting in the channel to the subscriber server, and its
+    /// highest value
+    pub channel_len:   u64,
+    pub channel_peak:  u64,
+    /// Messages the runner missed because it was too slow
+    pub lagged:        u64,
+    /// Messages sent through the client handle, and those which found no
+    /// receiver
+    pub messages_sent: u64,
+    pub send_errors:   u64,
+}
+
+impl<Type> Clone for Metrics<Type> {
+    fn clone(&self) -> Self {
+        Self {
+            events:        self.events.clone(),
+            by_subscriber: self.by_subscriber.clone(),
+            latency:       self.latency.clone(),
+            subscribers:   self.subscribers.clone(),
+            stored:        self.stored.clone(),
+            channel:       self.channel.clone(),
+            lagged:        self.lagged.clone(),
+            messages_sent: self.messages_sent.clone(),
+            send_errors:   self.send_errors.clone(),
+        }
+    }
+}
+
+impl<Type> Default for Metrics<Type> {
+    fn default() -> Self {
+        Self {
+            events:        Arc::new(Mutex::new(HashMap::new())),
+            by_subscriber: Arc::new(Mutex::new(HashMap::new())),
+            latency:       Histogram::default(),
+            subscribers:   Gauge::default(),
+            stored:        Gauge::default(),
+            channel:       Gauge::default(),
+            lagged:        Counter::default(),
+            messages_sent: Counter::default(),
+            send_errors:   Counter::default(),
+        }
+    }
+}
+
+impl<Type> Metrics<Type> {
+    #[must_use]
+    pub fn latency(&self) -> &Histogram {
+        &self.latency
+    }
+
+    #[must_use]
+    pub fn subscribers(&self) -> &Gauge {
+        &self.subscribers
+    }
+
+    #[must_use]
+    pub fn stored(&self) -> &Gauge {
+        &self.stored
+    }
+
+    #[must_use]
+    pub fn channel(&self) -> &Gauge {
+        &self.channel
+    }
+
+    #[must_use]
+    pub fn lagged(&self) -> &Counter {
+        &self.lagged
+    }
+
+    #[must_use]
+    pub fn messages_sent(&self) -> &Counter {
+        &self.messages_sent
+    }
+
+    #[must_use]
+    pub fn send_errors(&self) -> &Counter {
+        &self.send_errors
+    }
+
+    /// Update the counters of `subscriber`
+    pub fn subscriber<F: FnOnce(&mut SubscriberMetrics)>(
+        &self,
+        subscriber: SubscriberId,
+        update: F,
+    ) {
+        let mut subscribers = self.by_subscriber.lock().unwrap_or_else(PoisonError::into_inner);
+        update(subscribers.entry(subscriber).or_default());
+    }
+
+    /// Forget the counters of a subscriber which is gone
+    pub fn remove_subscriber(&self, subscriber: SubscriberId) {
+        let mut subscribers = self.by_subscriber.lock().unwrap_or_else(PoisonError::into_inner);
+        subscribers.remove(&subscriber);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Type, EventMetrics>> {
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Type: Eq + Hash + Clone> Metrics<Type> {
+    /// Update the counters of `event_type`
+    pub fn event<F: FnOnce(&mut EventMetrics)>(&self, event_type: &Type, update: F) {
+        let mut events = self.lock();
+        match events.get_mut(event_type) {
+            Some(metrics) => update(metrics),
+            None => update(events.entry(event_type.clone()).or_default()),
+        }
+    }
+
+    /// Record a successful callback call
+    pub fn delivered(&self, event_type: &Type, subscriber: SubscriberId, elapsed: Duration) {
+        self.event(event_type, |metrics| metrics.delivered += 1);
+        self.subscriber(subscriber, |metrics| metrics.delivered += 1);
+        self.latency.record(elapsed);
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot<Type> {
+        MetricsSnapshot {
+            events:        self.lock().clone(),
+            by_subscriber: self
+                .by_subscriber
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+            latency:       self.latency.snapshot(),
+            subscribers:   self.subscribers.get(),
+            stored:        self.stored.get(),
+            channel_len:   self.channel.get(),
+            channel_peak:  self.channel.peak(),
+            lagged:        self.lagged.get(),
+            messages_sent: self.messages_sent.get(),
+            send_errors:   self.send_errors.get(),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#![allow(clippy::module_name_repetitions)]
+pub use metrics_counter::{Counter, Gauge};
+pub use metrics_histogram::{Histogram, HistogramSnapshot, LATENCY_BUCKETS_US};
+pub use metrics_registry::{EventMetrics, Metrics, MetricsSnapshot, SubscriberMetrics};
+#[cfg(feature = "prometheus")]
+pub use metrics_prometheus::{render_prometheus, serve_prometheus, PROMETHEUS_CONTENT_TYPE, PROMETHEUS_NAMESPACE};
+
+mod metrics_counter;
+mod metrics_histogram;
+#[cfg(feature = "prometheus")]
+mod metrics_prometheus;
+mod metrics_registry;
@@ -0,0 +1,10 @@
+#![allow(clippy::module_name_repetitions, clippy::module_inception)]
+pub use source_set::{Source, SourceId, SourceItem, Sources};
+pub use source_signal::{Signal, Signals};
+pub use source_timer::{TimerId, TimerSchedule, Timers};
+pub use source_watch::{FileChange, WatchConfig, WatchId, Watches};
+
+mod source_set;
+mod source_signal;
+mod source_timer;
+mod source_watch;
@@ -0,0 +1,111 @@
+use futures_util::{
+    stream::{self, BoxStream, SelectAll},
+    StreamExt,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tracing::warn;
+
+use crate::{transport::ClientEvent, Message};
+
+/// Identifier of a source merged by the [`crate::Runner`], carried by every
+/// event it produces
+pub type SourceId = u64;
+
+/// Producer of events merged into a [`Sources`]
+pub enum Source<Event> {
+    /// A client backend, its messages and connection lifecycle
+    Client {
+        messages: broadcast::Receiver<Message>,
+        events:   broadcast::Receiver<ClientEvent>,
+    },
+    /// Events produced directly, such as timers or watches
+    Events(mpsc::Receiver<Event>),
+}
+
+/// What a [`Source`] produced
+#[derive(Debug)]
+pub enum SourceItem<Event> {
+    /// A message from a client, or the error which stopped its receiver
+    Message(Result<Message, RecvError>),
+    /// A lifecycle event from a client
+    Client(ClientEvent),
+    /// An event from an events source
+    Event(Event),
+}
+
+/// Dynamically growing set of sources polled as one, a source is dropped from
+/// the set once it closes
+pub struct Sources<Event> {
+    streams: SelectAll<BoxStream<'static, (SourceId, SourceItem<Event>)>>,
+}
+
+impl<Event> Default for Sources<Event> {
+    fn default() -> Self {
+        Self {
+            streams: SelectAll::new(),
+        }
+    }
+}
+
+impl<Event: Send + 'static> Sources<Event> {
+    pub fn push(&mut self, id: SourceId, source: Source<Event>) {
+        let items = match source {
+            Source::Client { messages, events } => stream::select(client_messages(messages), client_events(id, events)).boxed(),
+            Source::Events(receiver) => {
+                stream::unfold(receiver, |mut receiver| async move {
+                    receiver.recv().await.map(|event| (SourceItem::Event(event), receiver))
+                })
+                .boxed()
+            },
+        };
+        self.streams.push(items.map(move |item| (id, item)).boxed());
+    }
+
+    /// Next item of any source, `None` once the set is empty
+    pub async fn next(&mut self) -> Option<(SourceId, SourceItem<Event>)> {
+        self.streams.next().await
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+}
+
+fn client_messages<Event: Send + 'static>(
+    receiver: broadcast::Receiver<Message>,
+) -> BoxStream<'static, SourceItem<Event>> {
+    // the closing error is forwarded once before the stream ends
+    stream::unfold(Some(receiver), |receiver| async move {
+        let mut receiver = receiver?;
+        match receiver.recv().await {
+            Err(RecvError::Closed) => Some((SourceItem::Message(Err(RecvError::Closed)), None)),
+            res => Some((SourceItem::Message(res), Some(receiver))),
+        }
+    })
+    .boxed()
+}
+
+fn client_events<Event: Send + 'static>(
+    id: SourceId,
+    receiver: broadcast::Receiver<ClientEvent>,
+) -> BoxStream<'static, SourceItem<Event>> {
+    stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((SourceItem::Client(event), receiver)),
+                Err(RecvError::Lagged(count)) => warn!("Source {} missed {} client events", id, count),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .boxed()
+}
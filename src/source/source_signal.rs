@@ -0,0 +1,116 @@
+use std::{fmt::Debug, str::FromStr};
+
+use anyhow::anyhow;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
+
+use crate::subscriber::EventHandle;
+
+/// Unix signals forwarded by [`Signals`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// SIGINT
+    Interrupt,
+    /// SIGTERM
+    Terminate,
+    /// SIGHUP
+    Hangup,
+    /// SIGUSR1
+    User1,
+}
+
+impl FromStr for Signal {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "SIGINT" => Signal::Interrupt,
+            "SIGTERM" => Signal::Terminate,
+            "SIGHUP" => Signal::Hangup,
+            "SIGUSR1" => Signal::User1,
+            _ => return Err(anyhow!("Unknown signal {}", s)),
+        })
+    }
+}
+
+/// Signal source emitting events into a
+/// [`crate::subscriber::SubscriberServer`], it stops forwarding when disabled
+/// or dropped.
+///
+/// Once enabled the default behaviour of these signals is replaced for the
+/// lifetime of the process, a SIGINT no longer terminates it even after the
+/// source is disabled
+#[derive(Default)]
+pub struct Signals {
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Signals {
+    /// Start forwarding SIGINT/SIGTERM/SIGHUP/SIGUSR1 as the event built from
+    /// the [`Signal`], `on_terminate` is sent right after every SIGTERM.
+    /// Enabling again replaces the previous source
+    ///
+    /// # Errors
+    /// If the signal handlers can not be registered
+    pub fn enable<Event>(
+        &mut self,
+        runtime: &tokio::runtime::Handle,
+        send_handle: EventHandle<Event>,
+        on_terminate: Option<Event>,
+    ) -> anyhow::Result<()>
+    where
+        Event: From<Signal> + Clone + Debug + Send + 'static,
+    {
+        // registering needs the runtime but we want the error now
+        let _guard = runtime.enter();
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut hangup = signal(SignalKind::hangup())?;
+        let mut user1 = signal(SignalKind::user_defined1())?;
+
+        self.disable();
+        self.handle = Some(runtime.spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    Some(()) = interrupt.recv() => Signal::Interrupt,
+                    Some(()) = terminate.recv() => Signal::Terminate,
+                    Some(()) = hangup.recv() => Signal::Hangup,
+                    Some(()) = user1.recv() => Signal::User1,
+                    else => return,
+                };
+                info!("Received {:?}", received);
+                let mut events = vec![Event::from(received)];
+                if received == Signal::Terminate {
+                    events.extend(on_terminate.clone());
+                }
+                for event in events {
+                    if let Err(err) = send_handle.send(event).await {
+                        warn!("Signal source could not send its event {:?}", err);
+                        return;
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// Stop forwarding signals, returns false if the source was not enabled
+    pub fn disable(&mut self) -> bool {
+        match self.handle.take() {
+            Some(handle) => {
+                handle.abort();
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        self.disable();
+    }
+}
@@ -0,0 +1,144 @@
+use std::{collections::HashMap, fmt::Debug, str::FromStr, time::Duration};
+
+use chrono::Utc;
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+use tracing::{info, warn};
+
+use crate::{source::SourceId, subscriber::EventHandle};
+
+/// Identifier of a timer registered in [`Timers`], used to cancel it. The
+/// runner uses the id of the source the timer emits into
+pub type TimerId = SourceId;
+
+/// When a timer emits its event
+#[derive(Debug, Clone)]
+pub enum TimerSchedule {
+    /// Emit once after the delay
+    Once(Duration),
+    /// Emit at a fixed interval, the first event after one period
+    Interval(Duration),
+    /// Emit following a cron expression in UTC (`sec min hour day month
+    /// weekday [year]`)
+    Cron(Box<cron::Schedule>),
+}
+
+impl TimerSchedule {
+    /// Parse a cron expression such as `0 */5 * * * *`
+    ///
+    /// # Errors
+    /// If the expression is not valid
+    pub fn cron(expression: &str) -> anyhow::Result<Self> {
+        Ok(Self::Cron(Box::new(cron::Schedule::from_str(expression)?)))
+    }
+
+    /// # Errors
+    /// If the schedule can not be run, such as a zero interval
+    pub fn check(&self) -> anyhow::Result<()> {
+        match self {
+            TimerSchedule::Interval(period) if period.is_zero() => {
+                Err(anyhow::anyhow!("Interval should not be 0"))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Timers emitting events into a [`crate::subscriber::SubscriberServer`], all
+/// of them are cancelled when dropped
+#[derive(Debug, Default)]
+pub struct Timers {
+    handles: HashMap<TimerId, JoinHandle<()>>,
+}
+
+impl Timers {
+    /// Start a timer sending a clone of `event` on every tick, a timer already
+    /// registered as `id` is replaced
+    ///
+    /// # Errors
+    /// If the schedule can not be run, see [`TimerSchedule::check`]
+    pub fn add<Event>(
+        &mut self,
+        id: TimerId,
+        runtime: &tokio::runtime::Handle,
+        schedule: TimerSchedule,
+        send_handle: EventHandle<Event>,
+        event: Event,
+    ) -> anyhow::Result<()>
+    where
+        Event: Clone + Debug + Send + 'static,
+    {
+        schedule.check()?;
+        self.handles.retain(|_, handle| !handle.is_finished());
+        let handle = runtime.spawn(run(id, schedule, send_handle, event));
+        if let Some(previous) = self.handles.insert(id, handle) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Stop a timer, returns false if it is unknown or already finished
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        match self.handles.remove(&id) {
+            Some(handle) => {
+                let running = !handle.is_finished();
+                handle.abort();
+                running
+            },
+            None => false,
+        }
+    }
+}
+
+impl Drop for Timers {
+    fn drop(&mut self) {
+        for handle in self.handles.values() {
+            handle.abort();
+        }
+    }
+}
+
+async fn run<Event>(id: TimerId, schedule: TimerSchedule, send_handle: EventHandle<Event>, event: Event)
+where
+    Event: Clone + Debug + Send + 'static,
+{
+    match schedule {
+        TimerSchedule::Once(delay) => {
+            tokio::time::sleep(delay).await;
+            emit(id, &send_handle, event).await;
+        },
+        TimerSchedule::Interval(period) => {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if !emit(id, &send_handle, event.clone()).await {
+                    return;
+                }
+            }
+        },
+        TimerSchedule::Cron(schedule) => {
+            for next in schedule.upcoming(Utc) {
+                let delay = (next - Utc::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(delay).await;
+                if !emit(id, &send_handle, event.clone()).await {
+                    return;
+                }
+            }
+            info!("Timer {} has no upcoming date left", id);
+        },
+    }
+}
+
+/// Returns false once the subscriber server is gone
+async fn emit<Event: Debug>(id: TimerId, send_handle: &EventHandle<Event>, event: Event) -> bool {
+    match send_handle.send(event).await {
+        Ok(()) => true,
+        Err(err) => {
+            warn!("Timer {} could not send its event {:?}", id, err);
+            false
+        },
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Config,
+    EventKind,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::{info, warn};
+
+use crate::{source::SourceId, subscriber::EventHandle};
+
+/// Identifier of a watch registered in [`Watches`], used to remove it. The
+/// runner uses the id of the source the watch emits into
+pub type WatchId = SourceId;
+
+/// Kind of change reported for a watched path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    /// The path was created or renamed to
+    Created,
+    /// The content or metadata of the path changed
+    Modified,
+    /// The path was removed or renamed from
+    Removed,
+}
+
+/// How a path is watched
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Watch every path below a directory and not only its direct children
+    pub recursive: bool,
+    /// Changes are held until nothing happened for this delay, the changes of
+    /// one path are then merged into a single event. Zero emits every change
+    /// as soon as it is seen
+    pub debounce:  Duration,
+    /// Pending changes are emitted at the latest this long after the first of
+    /// them, even when a path keeps changing such as a log file. Never
+    /// shorter than `debounce`
+    pub max_wait:  Duration,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            debounce:  Duration::from_millis(100),
+            max_wait:  Duration::from_secs(1),
+        }
+    }
+}
+
+struct Watch {
+    // the watcher thread stops once dropped
+    _watcher: RecommendedWatcher,
+    handle:   JoinHandle<()>,
+}
+
+/// Filesystem watches emitting events into a
+/// [`crate::subscriber::SubscriberServer`], all of them are removed when
+/// dropped
+#[derive(Default)]
+pub struct Watches {
+    watches: HashMap<WatchId, Watch>,
+}
+
+impl Watches {
+    /// Start watching `path`, each change is sent as the event built from the
+    /// [`FileChange`] and the changed path. A watch already registered as
+    /// `id` is replaced
+    ///
+    /// # Errors
+    /// If the path does not exist or the watcher can not be created
+    pub fn add<Event>(
+        &mut self,
+        id: WatchId,
+        runtime: &tokio::runtime::Handle,
+        path: &Path,
+        config: WatchConfig,
+        send_handle: EventHandle<Event>,
+    ) -> anyhow::Result<()>
+    where
+        Event: From<(FileChange, String)> + Debug + Send + 'static,
+    {
+        let (sender, receiver) = unbounded_channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res| {
+                // the receiver is only gone once the watch was removed
+                let _ = sender.send(res);
+            },
+            Config::default(),
+        )?;
+        let mode = if config.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(path, mode)?;
+        info!("Watching {}", path.display());
+
+        let handle = runtime.spawn(run(id, receiver, config, send_handle));
+        self.watches.insert(id, Watch {
+            _watcher: watcher,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Stop a watch, returns false if it is unknown
+    pub fn remove(&mut self, id: WatchId) -> bool {
+        self.watches.remove(&id).is_some()
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn run<Event>(
+    id: WatchId,
+    mut receiver: UnboundedReceiver<notify::Result<notify::Event>>,
+    config: WatchConfig,
+    send_handle: EventHandle<Event>,
+) where
+    Event: From<(FileChange, String)> + Debug + Send + 'static,
+{
+    let max_wait = config.max_wait.max(config.debounce);
+    let mut pending: Vec<(PathBuf, FileChange)> = Vec::new();
+    // when the first pending change was seen and when they are emitted
+    let mut first = None;
+    let mut deadline = None;
+    loop {
+        tokio::select! {
+            res = receiver.recv() => match res {
+                Some(Ok(event)) => {
+                    for (path, change) in changes(event) {
+                        merge(&mut pending, path, change);
+                    }
+                    let now = Instant::now();
+                    let first = *first.get_or_insert(now);
+                    deadline = Some((now + config.debounce).min(first + max_wait));
+                },
+                Some(Err(err)) => warn!("Watch {} failed: {}", id, err),
+                None => return,
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                deadline = None;
+                first = None;
+                for (path, change) in pending.drain(..) {
+                    let event = Event::from((change, path.to_string_lossy().into_owned()));
+                    if let Err(err) = send_handle.send(event).await {
+                        warn!("Watch {} could not send its event {:?}", id, err);
+                        return;
+                    }
+                }
+            },
+        }
+    }
+}
+
+fn changes(event: notify::Event) -> Vec<(PathBuf, FileChange)> {
+    let change = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => FileChange::Created,
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => FileChange::Removed,
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+            let mut paths = event.paths.into_iter();
+            return paths
+                .next()
+                .map(|from| (from, FileChange::Removed))
+                .into_iter()
+                .chain(paths.next().map(|to| (to, FileChange::Created)))
+                .collect();
+        },
+        EventKind::Modify(_) | EventKind::Any => FileChange::Modified,
+        EventKind::Access(_) | EventKind::Other => return Vec::new(),
+    };
+    event.paths.into_iter().map(|path| (path, change)).collect()
+}
+
+/// Fold a new change of a path into the one still pending for it
+fn merge(pending: &mut Vec<(PathBuf, FileChange)>, path: PathBuf, change: FileChange) {
+    let index = match pending.iter().position(|(p, _)| *p == path) {
+        Some(index) => index,
+        None => {
+            pending.push((path, change));
+            return;
+        },
+    };
+    match (pending[index].1, change) {
+        // the path never existed for the subscribers
+        (FileChange::Created, FileChange::Removed) => {
+            pending.remove(index);
+        },
+        (FileChange::Created, _) => {},
+        (FileChange::Removed, FileChange::Created) => pending[index].1 = FileChange::Modified,
+        (_, change) => pending[index].1 = change,
+    }
+}
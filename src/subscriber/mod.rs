@@ -1,20 +1,30 @@
 #![allow(clippy::module_name_repetitions, clippy::module_inception)]
 pub use subscriber::Subscriber;
+pub use subscriber_breaker::{BreakerPolicy, CircuitBreaker, Suspension};
 pub use subscriber_callback::SubscriberCallback;
 pub use subscriber_config::SubscriberConfig;
+pub use subscriber_dead_letter::{DeadLetter, DeadLetters, RetryPolicy, SubscriberId};
 pub use subscriber_error::SubscriberError;
 pub use subscriber_event::SubscriberEvent;
 pub use subscriber_event_type::SubscriberEventType;
-pub use subscriber_server::SubscriberServer;
-pub use subscriber_server_handle::{EventHandle, SubscribeHandle, SubscriberServerHandle};
-pub use subscribers::Subscribers;
+pub use subscriber_interceptor::{SubscriberHandler, SubscriberInterceptor};
+pub use subscriber_middleware::{LoggingMiddleware, SubscriberMiddleware, TimingMiddleware, TimingStats};
+pub use subscriber_server::{MiddlewareHandle, SubscriberServer};
+pub use subscriber_server_handle::{EventHandle, Subscribe, SubscribeHandle, SubscriberServerHandle, EVENT_CHANNEL_SIZE};
+pub use subscribers::{DispatchOrder, SubscriberEntry, Subscribers};
+pub use subscription::{EventFilter, Subscription};
 
 mod subscriber;
+mod subscriber_breaker;
 mod subscriber_callback;
 mod subscriber_config;
+mod subscriber_dead_letter;
 mod subscriber_error;
 mod subscriber_event;
 mod subscriber_event_type;
+mod subscriber_interceptor;
+mod subscriber_middleware;
 mod subscriber_server;
 mod subscriber_server_handle;
 mod subscribers;
+mod subscription;
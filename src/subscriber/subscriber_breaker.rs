@@ -0,0 +1,126 @@
+use std::{
+    cell::Cell,
+    fmt::{Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use crate::subscriber::SubscriberId;
+
+/// When a failing subscriber stops being called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerPolicy {
+    /// Consecutive failures which trip the breaker, 0 never trips it
+    pub failure_threshold: u32,
+    /// How long a tripped subscriber is suspended, it is removed when none
+    pub cooldown:          Option<Duration>,
+}
+
+impl Default for BreakerPolicy {
+    fn default() -> Self {
+        Self::new(5, Some(Duration::from_secs(30)))
+    }
+}
+
+impl BreakerPolicy {
+    #[must_use]
+    pub fn new(failure_threshold: u32, cooldown: Option<Duration>) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+        }
+    }
+
+    /// Subscribers are called whatever happens
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self::new(0, None)
+    }
+}
+
+/// A subscriber stopped being called after failing too many times in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suspension {
+    pub subscriber: SubscriberId,
+    pub failures:   u32,
+    /// None when the subscriber was removed for good
+    pub cooldown:   Option<Duration>,
+}
+
+impl Display for Suspension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.cooldown {
+            Some(cooldown) => write!(
+                f,
+                "subscriber={} failures={} cooldown_ms={}",
+                self.subscriber,
+                self.failures,
+                cooldown.as_millis()
+            ),
+            None => write!(f, "subscriber={} failures={} removed", self.subscriber, self.failures),
+        }
+    }
+}
+
+/// Counts the consecutive failures of a subscriber. Once tripped the
+/// subscriber is skipped until the cooldown elapsed, then a single failure
+/// trips it again while a success closes it
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    policy:          BreakerPolicy,
+    failures:        Cell<u32>,
+    suspended_until: Cell<Option<Instant>>,
+    removed:         Cell<bool>,
+}
+
+impl CircuitBreaker {
+    #[must_use]
+    pub fn new(policy: BreakerPolicy) -> Self {
+        Self {
+            policy,
+            failures: Cell::new(0),
+            suspended_until: Cell::new(None),
+            removed: Cell::new(false),
+        }
+    }
+
+    /// Whether the subscriber can be called now
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        if self.removed.get() {
+            return false;
+        }
+        match self.suspended_until.get() {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+
+    /// Whether the subscriber was removed for good
+    #[must_use]
+    pub fn is_removed(&self) -> bool {
+        self.removed.get()
+    }
+
+    pub fn record_success(&self) {
+        self.failures.set(0);
+        self.suspended_until.set(None);
+    }
+
+    /// Returns the suspension when this failure trips the breaker
+    pub fn record_failure(&self, subscriber: SubscriberId) -> Option<Suspension> {
+        let failures = self.failures.get().saturating_add(1);
+        self.failures.set(failures);
+        if self.policy.failure_threshold == 0 || failures < self.policy.failure_threshold {
+            return None;
+        }
+        match self.policy.cooldown {
+            Some(cooldown) => self.suspended_until.set(Some(Instant::now() + cooldown)),
+            None => self.removed.set(true),
+        }
+        Some(Suspension {
+            subscriber,
+            failures,
+            cooldown: self.policy.cooldown,
+        })
+    }
+}
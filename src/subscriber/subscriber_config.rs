@@ -1,7 +1,26 @@
+use crate::subscriber::{BreakerPolicy, RetryPolicy};
+
 pub trait SubscriberConfig: Default + Send {
     /// Number of subscriber to be spawned at once
     fn subscriber_count(&self) -> usize;
 
     /// Channel size for the Subscriber
     fn channel_size(&self) -> usize;
+
+    /// Number of failed deliveries kept as dead letters
+    fn dead_letter_capacity(&self) -> usize {
+        1024
+    }
+
+    /// How failed deliveries are retried before becoming dead letters
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::none()
+    }
+
+    /// When a subscriber failing in a row is suspended or removed, never by
+    /// default, return [`BreakerPolicy::default`] or a policy of its own to
+    /// opt in
+    fn breaker_policy(&self) -> BreakerPolicy {
+        BreakerPolicy::disabled()
+    }
 }
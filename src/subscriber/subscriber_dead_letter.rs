@@ -0,0 +1,183 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+/// Identifier of a subscriber within its
+/// [`crate::subscriber::SubscriberServer`], in the order of subscription
+/// starting at 1
+pub type SubscriberId = u64;
+
+/// How many times a failed delivery is attempted before the event becomes a
+/// [`DeadLetter`], and how long to wait between attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts including the first delivery, 1 never retries
+    pub max_attempts: u32,
+    /// Delay before the first retry
+    pub backoff:      Duration,
+    /// Factor applied to the delay after each retry
+    pub multiplier:   f64,
+    /// Upper bound of the delay
+    pub max_backoff:  Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Failed deliveries go straight to the dead letters
+    #[must_use]
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO)
+    }
+
+    /// Retry up to `max_attempts` deliveries, doubling `backoff` between them
+    #[must_use]
+    pub fn new(max_attempts: u32, backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff,
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+
+    #[must_use]
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    #[must_use]
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Whether a delivery which failed `attempts` times is tried again
+    #[must_use]
+    pub fn should_retry(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+
+    /// Delay before the next delivery once `attempts` failed
+    #[must_use]
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::from_secs_f64(delay.min(self.max_backoff.as_secs_f64()))
+    }
+}
+
+/// Event which could not be delivered to a subscriber
+#[derive(Debug, Clone)]
+pub struct DeadLetter<Event> {
+    pub subscriber: SubscriberId,
+    pub event:      Event,
+    /// Error of the last attempt
+    pub error:      String,
+    pub attempts:   u32,
+    pub failed_at:  SystemTime,
+}
+
+/// Bounded queue of [`DeadLetter`] shared between a
+/// [`crate::subscriber::SubscriberServer`] and its users, the oldest letters
+/// are dropped once it is full
+#[derive(Debug)]
+pub struct DeadLetters<Event> {
+    inner: Arc<Mutex<DeadLetterQueue<Event>>>,
+}
+
+#[derive(Debug)]
+struct DeadLetterQueue<Event> {
+    letters:      VecDeque<DeadLetter<Event>>,
+    capacity:     usize,
+    dropped:      u64,
+    retry_policy: RetryPolicy,
+}
+
+impl<Event> Clone for DeadLetters<Event> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<Event> DeadLetters<Event> {
+    #[must_use]
+    pub fn new(capacity: usize, retry_policy: RetryPolicy) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(DeadLetterQueue {
+                letters: VecDeque::new(),
+                capacity,
+                dropped: 0,
+                retry_policy,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DeadLetterQueue<Event>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().letters.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().letters.is_empty()
+    }
+
+    /// Letters dropped because the queue was full
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.lock().dropped
+    }
+
+    /// Remove and return up to `max` letters, oldest first
+    pub fn drain(&self, max: usize) -> Vec<DeadLetter<Event>> {
+        let mut queue = self.lock();
+        let count = max.min(queue.letters.len());
+        queue.letters.drain(..count).collect()
+    }
+
+    #[must_use]
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.lock().retry_policy
+    }
+
+    /// Applies to the deliveries failing from now on
+    pub fn set_retry_policy(&self, retry_policy: RetryPolicy) {
+        self.lock().retry_policy = retry_policy;
+    }
+
+    pub(crate) fn push(&self, letter: DeadLetter<Event>) {
+        let mut queue = self.lock();
+        if queue.capacity == 0 {
+            queue.dropped += 1;
+            return;
+        }
+        while queue.letters.len() >= queue.capacity {
+            queue.letters.pop_front();
+            queue.dropped += 1;
+        }
+        queue.letters.push_back(letter);
+    }
+}
+
+impl<Event: Clone> DeadLetters<Event> {
+    /// Copy of the letters, oldest first
+    #[must_use]
+    pub fn inspect(&self) -> Vec<DeadLetter<Event>> {
+        self.lock().letters.iter().cloned().collect()
+    }
+}
@@ -1,4 +1,7 @@
-use crate::subscriber::SubscriberEventType;
+use crate::{
+    subscriber::{SubscriberEventType, Suspension},
+    trace::TraceContext,
+};
 use std::fmt::Debug;
 
 pub trait SubscriberEvent: Clone + Send + Sync + Sized + Debug {
@@ -9,4 +12,20 @@ pub trait SubscriberEvent: Clone + Send + Sync + Sized + Debug {
 
     /// Returns the type of that event
     fn get_type(&self) -> Self::Type;
+
+    /// Textual content of that event used by payload filters, if any
+    fn payload(&self) -> Option<&str> {
+        None
+    }
+
+    /// Trace the event belongs to, its callbacks run in a child span
+    fn trace(&self) -> Option<&TraceContext> {
+        None
+    }
+
+    /// Event announcing that a subscriber was suspended, if the events can
+    /// describe it
+    fn suspended(_suspension: &Suspension) -> Option<Self> {
+        None
+    }
 }
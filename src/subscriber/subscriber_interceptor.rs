@@ -0,0 +1,39 @@
+use std::{
+    fmt::{Debug, Formatter},
+    panic::RefUnwindSafe,
+};
+
+use crate::subscriber::SubscriberEvent;
+
+/// Runs before the subscribers of an event, it can modify the event and
+/// returns false to veto it
+pub trait SubscriberInterceptor<Event>
+where
+    Self: Fn(&mut Event) -> bool + Send + Sync + RefUnwindSafe,
+    Event: SubscriberEvent,
+{
+}
+
+impl<F, Event> SubscriberInterceptor<Event> for F
+where
+    F: Fn(&mut Event) -> bool + Send + Sync + RefUnwindSafe,
+    Event: SubscriberEvent,
+{
+}
+
+/// What is registered for a [`crate::subscriber::Subscription`]
+pub enum SubscriberHandler<Event, Callback> {
+    /// Receives the events once every interceptor accepted them
+    Callback(Callback),
+    /// Sees the events first and can modify or veto them
+    Interceptor(Box<dyn SubscriberInterceptor<Event>>),
+}
+
+impl<Event, Callback> Debug for SubscriberHandler<Event, Callback> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubscriberHandler::Callback(_) => write!(f, "Callback"),
+            SubscriberHandler::Interceptor(_) => write!(f, "Interceptor"),
+        }
+    }
+}
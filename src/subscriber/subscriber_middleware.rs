@@ -0,0 +1,186 @@
+use std::{
+    error::Error,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tracing::{debug, error, info, trace, warn, Level};
+
+use crate::subscriber::SubscriberEvent;
+
+/// Hooks run by a [`crate::subscriber::SubscriberServer`] around every event,
+/// in their registration order. They run on the server task and should not
+/// block
+pub trait SubscriberMiddleware<Event>: Debug + Send + Sync {
+    /// Called once when the event reaches the server, before the interceptors,
+    /// it can modify the event or return false to drop it
+    fn before_publish(&self, _event: &mut Event) -> bool {
+        true
+    }
+
+    /// Called with the copy given to each subscriber, it can modify it or
+    /// return false to skip this subscriber
+    fn before_deliver(&self, _event: &mut Event) -> bool {
+        true
+    }
+
+    /// Called once a subscriber accepted the event, with the copy modified by
+    /// [`Self::before_deliver`] and the time it took
+    fn after_deliver(&self, _event: &Event, _elapsed: Duration) {}
+
+    /// Called when a subscriber failed to handle the event, with the copy
+    /// modified by [`Self::before_deliver`]
+    fn on_error(&self, _event: &Event, _error: &dyn Error) {}
+}
+
+/// Share a middleware to keep reading its state once registered
+impl<Event, Middleware> SubscriberMiddleware<Event> for Arc<Middleware>
+where
+    Middleware: SubscriberMiddleware<Event>,
+{
+    fn before_publish(&self, event: &mut Event) -> bool {
+        self.as_ref().before_publish(event)
+    }
+
+    fn before_deliver(&self, event: &mut Event) -> bool {
+        self.as_ref().before_deliver(event)
+    }
+
+    fn after_deliver(&self, event: &Event, elapsed: Duration) {
+        self.as_ref().after_deliver(event, elapsed);
+    }
+
+    fn on_error(&self, event: &Event, error: &dyn Error) {
+        self.as_ref().on_error(event, error);
+    }
+}
+
+/// Logs each step of the dispatch at `level`, errors are always logged as
+/// errors
+#[derive(Debug)]
+pub struct LoggingMiddleware {
+    level: Level,
+}
+
+impl Default for LoggingMiddleware {
+    fn default() -> Self {
+        Self::new(Level::DEBUG)
+    }
+}
+
+impl LoggingMiddleware {
+    #[must_use]
+    pub fn new(level: Level) -> Self {
+        Self { level }
+    }
+
+    fn log(&self, step: &str, event: &dyn Debug) {
+        match self.level {
+            Level::ERROR => error!("{} {:?}", step, event),
+            Level::WARN => warn!("{} {:?}", step, event),
+            Level::INFO => info!("{} {:?}", step, event),
+            Level::DEBUG => debug!("{} {:?}", step, event),
+            Level::TRACE => trace!("{} {:?}", step, event),
+        }
+    }
+}
+
+impl<Event: SubscriberEvent> SubscriberMiddleware<Event> for LoggingMiddleware {
+    fn before_publish(&self, event: &mut Event) -> bool {
+        self.log("Publishing", event);
+        true
+    }
+
+    fn before_deliver(&self, event: &mut Event) -> bool {
+        self.log("Delivering", event);
+        true
+    }
+
+    fn after_deliver(&self, event: &Event, elapsed: Duration) {
+        self.log(&format!("Delivered in {:?}", elapsed), event);
+    }
+
+    fn on_error(&self, event: &Event, error: &dyn Error) {
+        error!("Could not deliver {:?}: {}", event, error);
+    }
+}
+
+/// Measures how long subscribers take, warns about the deliveries slower than
+/// its threshold
+#[derive(Debug)]
+pub struct TimingMiddleware {
+    threshold:   Duration,
+    deliveries:  AtomicU64,
+    errors:      AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos:   AtomicU64,
+}
+
+/// Totals collected by a [`TimingMiddleware`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    pub deliveries: u64,
+    pub errors:     u64,
+    pub total:      Duration,
+    pub max:        Duration,
+}
+
+impl TimingStats {
+    /// Mean delivery time, zero before the first delivery
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        match self.deliveries {
+            0 => Duration::ZERO,
+            deliveries => Duration::from_nanos((self.total.as_nanos() / u128::from(deliveries)) as u64),
+        }
+    }
+}
+
+impl Default for TimingMiddleware {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100))
+    }
+}
+
+impl TimingMiddleware {
+    #[must_use]
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            deliveries: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            total_nanos: AtomicU64::new(0),
+            max_nanos: AtomicU64::new(0),
+        }
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> TimingStats {
+        TimingStats {
+            deliveries: self.deliveries.load(Ordering::Relaxed),
+            errors:     self.errors.load(Ordering::Relaxed),
+            total:      Duration::from_nanos(self.total_nanos.load(Ordering::Relaxed)),
+            max:        Duration::from_nanos(self.max_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<Event: SubscriberEvent> SubscriberMiddleware<Event> for TimingMiddleware {
+    fn after_deliver(&self, event: &Event, elapsed: Duration) {
+        let nanos = elapsed.as_nanos() as u64;
+        self.deliveries.fetch_add(1, Ordering::Relaxed);
+        self.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+        if elapsed > self.threshold {
+            warn!("Slow subscriber, took {:?} for {:?}", elapsed, event);
+        }
+    }
+
+    fn on_error(&self, _event: &Event, _error: &dyn Error) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
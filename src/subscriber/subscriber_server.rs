@@ -1,26 +1,116 @@
-use crate::subscriber::{
+use crate::{metrics::Metrics, subscriber::{
     subscriber_error::SubscriberError,
+    subscribers::delivery_span,
+    CircuitBreaker,
+    DeadLetter,
+    DeadLetters,
+    DispatchOrder,
     Subscriber,
     SubscriberCallback,
     SubscriberConfig,
+    SubscriberEntry,
     SubscriberEvent,
     SubscriberEventType,
+    SubscriberHandler,
+    SubscriberId,
+    SubscriberInterceptor,
+    SubscriberMiddleware,
     Subscribers,
-};
+    Subscription,
+    Suspension,
+}};
 use std::{
     borrow::BorrowMut,
+    cell::Cell,
     collections::{HashMap, VecDeque},
     fmt::Debug,
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::{Instant, SystemTime},
 };
-use tokio::sync::mpsc::Receiver;
-use tracing::{info, warn};
+use tokio::sync::mpsc::{unbounded_channel, Receiver, UnboundedReceiver, UnboundedSender};
+use tracing::{info, trace, warn};
 
 
+/// Dispatches each event in a deterministic order:
+/// 0. the `before_publish` hook of every middleware, in registration order
+/// 1. the matching interceptors, by descending priority then subscription
+///    order, each one sees the event as modified by the previous ones and the
+///    first veto drops the event
+/// 2. the matching subscribers, by descending priority then subscription
+///    order, whether they subscribed to the event type or through a filter,
+///    each delivery is wrapped by the delivery hooks of the middlewares
+///
+/// A failed delivery is retried for that subscriber alone following the
+/// [`crate::subscriber::RetryPolicy`] of its [`DeadLetters`], the event
+/// becomes a [`DeadLetter`] once the attempts are exhausted. Each subscriber
+/// has a [`CircuitBreaker`] following the
+/// [`crate::subscriber::BreakerPolicy`] of the config, when it trips the
+/// event built by [`SubscriberEvent::suspended`] is published
 #[derive(Debug)]
 pub struct SubscriberServer<Type, Event, Config, Sub> {
     store: HashMap<Type, VecDeque<Event>>,
     config: Config,
     subscribers: HashMap<Type, Subscribers<Sub>>,
+    // subscriptions which are not a plain single type, checked on every event
+    filtered: Vec<(Subscription<Type, Event>, SubscriberEntry<Sub>)>,
+    interceptors: Vec<(DispatchOrder, Subscription<Type, Event>, Interceptor<Event>)>,
+    middlewares: Vec<Box<dyn SubscriberMiddleware<Event>>>,
+    next_sequence: u64,
+    dead_letters: DeadLetters<Event>,
+    send_internal: UnboundedSender<Internal<Event>>,
+    recv_internal: Option<UnboundedReceiver<Internal<Event>>>,
+    // a breaker removed a subscriber which has to be dropped
+    removed: Cell<bool>,
+    metrics: Metrics<Type>,
+}
+
+/// Work the server schedules for itself
+#[derive(Debug)]
+enum Internal<Event> {
+    /// Delivery due again to a single subscriber
+    Retry {
+        subscriber: SubscriberId,
+        event: Event,
+        attempts: u32,
+    },
+    /// Event raised by the server such as a suspension
+    Publish(Event),
+    /// Middleware registered once the server runs
+    Middleware(Box<dyn SubscriberMiddleware<Event>>),
+}
+
+/// Registers middlewares on a [`SubscriberServer`] which already runs, they
+/// apply to the events dispatched once the server picked them up
+#[derive(Debug)]
+pub struct MiddlewareHandle<Event> {
+    send_internal: UnboundedSender<Internal<Event>>,
+}
+
+impl<Event> Clone for MiddlewareHandle<Event> {
+    fn clone(&self) -> Self {
+        Self { send_internal: self.send_internal.clone() }
+    }
+}
+
+impl<Event> MiddlewareHandle<Event> {
+    /// Add a middleware after the ones already registered, fails when the
+    /// server is gone
+    pub fn add<Middleware>(&self, middleware: Middleware) -> anyhow::Result<()>
+        where
+            Middleware: SubscriberMiddleware<Event> + 'static,
+    {
+        self.send_internal
+            .send(Internal::Middleware(Box::new(middleware)))
+            .map_err(|_| anyhow::anyhow!("Subscriber server is gone"))
+    }
+}
+
+struct Interceptor<Event>(Box<dyn SubscriberInterceptor<Event>>);
+
+impl<Event> Debug for Interceptor<Event> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interceptor")
+    }
 }
 
 impl<Type, Event, Config, Sub> Default for SubscriberServer<Type, Event, Config, Sub>
@@ -37,44 +127,106 @@ impl<Type, Event, Config, Sub> SubscriberServer<Type, Event, Config, Sub>
         Config: SubscriberConfig,
 {
     pub fn new(config: Config) -> Self {
+        let dead_letters = DeadLetters::new(config.dead_letter_capacity(), config.retry_policy());
+        let (send_internal, recv_internal) = unbounded_channel();
         Self {
             store: HashMap::with_capacity(16 + 2 + 1 + 1),
             config,
             subscribers: HashMap::with_capacity(10),
+            filtered: Vec::new(),
+            interceptors: Vec::new(),
+            middlewares: Vec::new(),
+            next_sequence: 0,
+            dead_letters,
+            send_internal,
+            recv_internal: Some(recv_internal),
+            removed: Cell::new(false),
+            metrics: Metrics::default(),
         }
     }
+
+    /// Counters of this server, shared with the clones
+    #[must_use]
+    pub fn metrics(&self) -> Metrics<Type> {
+        self.metrics.clone()
+    }
+
+    /// Record into `metrics` instead of a registry of its own
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Metrics<Type>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Handle on the failed deliveries of this server, to inspect them or
+    /// change the retry policy once the server runs
+    #[must_use]
+    pub fn dead_letters(&self) -> DeadLetters<Event> {
+        self.dead_letters.clone()
+    }
+
+    /// Add a middleware after the ones already registered
+    #[must_use]
+    pub fn with_middleware<Middleware>(mut self, middleware: Middleware) -> Self
+        where
+            Middleware: SubscriberMiddleware<Event> + 'static,
+    {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    /// Handle to add middlewares once the server runs
+    #[must_use]
+    pub fn middleware_handle(&self) -> MiddlewareHandle<Event> {
+        MiddlewareHandle { send_internal: self.send_internal.clone() }
+    }
 }
 
 impl<Type, Event, Config, Sub> SubscriberServer<Type, Event, Config, Sub>
     where
-        Event: SubscriberEvent<Type=Type>,
+        Event: SubscriberEvent<Type=Type> + 'static,
         Config: SubscriberConfig,
         Type: SubscriberEventType,
 {
     pub async fn run<Error, Callback>(
         mut self,
         mut recv_event: Receiver<Event>,
-        mut recv_subscribe: Receiver<(Type, Callback)>,
+        mut recv_subscribe: Receiver<(Subscription<Type, Event>, SubscriberHandler<Event, Callback>)>,
     ) where
         Error: SubscriberError,
         Sub: Subscriber<Event, Error>,
         Callback: SubscriberCallback<Event> + 'static,
     {
+        let mut recv_internal = match self.recv_internal.take() {
+            Some(recv_internal) => recv_internal,
+            None => return,
+        };
         loop {
             // Do subscription
             tokio::select! {
-                Some((event_type, callback)) = recv_subscribe.recv() =>{
-                    self.borrow_mut().subscribe(event_type, callback);
+                Some((subscription, handler)) = recv_subscribe.recv() =>{
+                    self.borrow_mut().subscribe(subscription, handler);
                 }
-                Some(event) = recv_event.recv() => {
+                event = recv_event.recv() => {
+                    let event = match event {
+                        Some(event) => event,
+                        None => break,
+                    };
                     if event.should_kill() {
                         info!("Killing Subscriber server");
                         return;
                     }
                     self.borrow_mut().send(event);
                 }
-                else => {
-                    break;
+                // the server keeps a sender so this never closes
+                Some(internal) = recv_internal.recv() => {
+                    match internal {
+                        Internal::Retry { subscriber, event, attempts } => {
+                            self.borrow_mut().retry(subscriber, &event, attempts);
+                        },
+                        Internal::Publish(event) => self.borrow_mut().send(event),
+                        Internal::Middleware(middleware) => self.middlewares.push(middleware),
+                    }
                 }
             }
         }
@@ -84,55 +236,273 @@ impl<Type, Event, Config, Sub> SubscriberServer<Type, Event, Config, Sub>
 impl<Type, Event, Config, Sub> SubscriberServer<Type, Event, Config, Sub>
     where
         Type: SubscriberEventType,
-        Event: SubscriberEvent<Type=Type>,
+        Event: SubscriberEvent<Type=Type> + 'static,
         Config: SubscriberConfig,
 {
-    fn send<Error>(&mut self, event: Event)
+    fn send<Error>(&mut self, mut event: Event)
         where
             Error: SubscriberError,
             Sub: Subscriber<Event, Error> ,
     {
-        let event_type = event.get_type();
-        if let Some(subscribers) = self.subscribers.get(&event_type) {
-            if let Some(err) = subscribers.notify(event) {
-                warn!("Could not notify subscribers {:?}",err);
+        self.metrics.event(&event.get_type(), |metrics| metrics.published += 1);
+        for middleware in &self.middlewares {
+            if !middleware.before_publish(&mut event) {
+                trace!("Event dropped by a middleware {:?}", middleware);
+                self.metrics.event(&event.get_type(), |metrics| metrics.dropped += 1);
+                return;
             }
-        } else {
+        }
+        for (_, subscription, interceptor) in &self.interceptors {
+            if !subscription.matches(&event) {
+                continue;
+            }
+            match catch_unwind(AssertUnwindSafe(|| (interceptor.0)(&mut event))) {
+                Ok(true) => {},
+                Ok(false) => {
+                    trace!("Event vetoed by an interceptor {:?}", subscription);
+                    self.metrics.event(&event.get_type(), |metrics| metrics.dropped += 1);
+                    return;
+                },
+                Err(err) => {
+                    warn!("Interceptor panicked, dropping the event {:?}", err);
+                    self.metrics.event(&event.get_type(), |metrics| metrics.dropped += 1);
+                    return;
+                },
+            }
+        }
+
+        let event_type = event.get_type();
+        let mut subscribers: Vec<&SubscriberEntry<Sub>> = self.subscribers
+            .get(&event_type)
+            .map(|subscribers| subscribers.iter().collect())
+            .unwrap_or_default();
+        let exact = subscribers.len();
+        subscribers.extend(self.filtered
+            .iter()
+            .filter(|(subscription, _)| subscription.matches(&event))
+            .map(|(_, entry)| entry));
+        if subscribers.is_empty() {
+            self.metrics.event(&event_type, |metrics| metrics.stored += 1);
             let vec = self.store
-                .entry(event_type)
+                .entry(event_type.clone())
                 .or_insert_with(|| VecDeque::with_capacity(self.config.channel_size()));
+            let mut evicted = 0;
             while vec.len() > 10 {
                 let _dropped = vec.pop_front();
+                evicted += 1;
             }
             vec.push_back(event);
+            self.metrics.stored().inc();
+            if evicted > 0 {
+                self.metrics.stored().sub(evicted);
+                self.metrics.event(&event_type, |metrics| metrics.dropped += evicted);
+            }
+            return;
+        }
+        if exact != subscribers.len() {
+            subscribers.sort_by_key(|entry| entry.order);
+        }
+        for entry in subscribers {
+            if entry.breaker.is_closed() {
+                self.deliver(entry, &event, 0);
+            }
+        }
+        self.drop_removed();
+    }
+
+    fn retry<Error>(&mut self, subscriber: SubscriberId, event: &Event, attempts: u32)
+        where
+            Error: SubscriberError,
+            Sub: Subscriber<Event, Error>,
+    {
+        let entry = self.subscribers
+            .values()
+            .flat_map(Subscribers::iter)
+            .chain(self.filtered.iter().map(|(_, entry)| entry))
+            .find(|entry| entry.id() == subscriber);
+        match entry {
+            Some(entry) if entry.breaker.is_closed() => self.deliver(entry, event, attempts),
+            Some(_) => self.dead_letters.push(DeadLetter {
+                subscriber,
+                event: event.clone(),
+                error: String::from("Subscriber is suspended"),
+                attempts,
+                failed_at: SystemTime::now(),
+            }),
+            None => warn!("Subscriber {} is gone, could not retry {:?}", subscriber, event),
+        }
+        self.drop_removed();
+    }
+
+    fn drop_removed(&mut self) {
+        if !self.removed.replace(false) {
+            return;
+        }
+        let removed = self.subscribers
+            .values()
+            .flat_map(Subscribers::iter)
+            .chain(self.filtered.iter().map(|(_, entry)| entry))
+            .filter(|entry| entry.breaker.is_removed())
+            .map(SubscriberEntry::id);
+        for id in removed {
+            self.metrics.remove_subscriber(id);
+        }
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.retain_active();
+        }
+        self.filtered.retain(|(_, entry)| !entry.breaker.is_removed());
+        self.count_subscribers();
+    }
+
+    fn count_subscribers(&self) {
+        let exact: usize = self.subscribers.values().map(Subscribers::len).sum();
+        self.metrics.subscribers().set((exact + self.filtered.len()) as u64);
+    }
+
+    /// Notify a subscriber with a copy of `event` through the delivery hooks,
+    /// a failure is retried or becomes a dead letter and counts towards its
+    /// breaker
+    fn deliver<Error>(&self, entry: &SubscriberEntry<Sub>, event: &Event, attempts: u32)
+        where
+            Error: SubscriberError,
+            Sub: Subscriber<Event, Error>,
+    {
+        let id = entry.id();
+        let mut delivered = event.clone();
+        for middleware in &self.middlewares {
+            if !middleware.before_deliver(&mut delivered) {
+                return;
+            }
+        }
+        let span = delivery_span(event, id);
+        let _entered = span.enter();
+        let start = Instant::now();
+        // the delivery hooks see the copy as the subscriber received it
+        let err = match entry.sub.notify(delivered.clone()) {
+            Ok(()) => {
+                entry.breaker.record_success();
+                let elapsed = start.elapsed();
+                self.metrics.delivered(&event.get_type(), id, elapsed);
+                for middleware in &self.middlewares {
+                    middleware.after_deliver(&delivered, elapsed);
+                }
+                return;
+            },
+            Err(err) => err,
+        };
+        warn!("Could not notify subscriber {} {:?}", id, err);
+        self.metrics.event(&event.get_type(), |metrics| metrics.failed += 1);
+        self.metrics.subscriber(id, |metrics| metrics.failed += 1);
+        for middleware in &self.middlewares {
+            middleware.on_error(&delivered, &err);
         }
+        let attempts = attempts + 1;
+        let suspension = entry.breaker.record_failure(id);
+        if let Some(suspension) = suspension {
+            self.suspend(suspension);
+        }
+        let policy = self.dead_letters.retry_policy();
+        if suspension.is_none() && policy.should_retry(attempts) {
+            let delay = policy.delay(attempts);
+            let send_internal = self.send_internal.clone();
+            let retry = Internal::Retry { subscriber: id, event: event.clone(), attempts };
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                // the server is gone otherwise
+                let _ = send_internal.send(retry);
+            });
+            return;
+        }
+        self.dead_letters.push(DeadLetter {
+            subscriber: id,
+            event: event.clone(),
+            error: err.to_string(),
+            attempts,
+            failed_at: SystemTime::now(),
+        });
+    }
 
+    fn suspend(&self, suspension: Suspension) {
+        self.metrics.subscriber(suspension.subscriber, |metrics| metrics.suspensions += 1);
+        match suspension.cooldown {
+            Some(cooldown) => warn!(
+                "Subscriber {} suspended for {:?} after {} consecutive failures",
+                suspension.subscriber, cooldown, suspension.failures
+            ),
+            None => {
+                warn!(
+                    "Subscriber {} removed after {} consecutive failures",
+                    suspension.subscriber, suspension.failures
+                );
+                self.removed.set(true);
+            },
+        }
+        if let Some(event) = Event::suspended(&suspension) {
+            // published once the current dispatch is over
+            let _ = self.send_internal.send(Internal::Publish(event));
+        }
     }
 }
 
 impl<Type, Event, Config, Sub> SubscriberServer<Type, Event, Config, Sub>
     where
         Type: SubscriberEventType,
-        Event: SubscriberEvent,
+        Event: SubscriberEvent<Type=Type> + 'static,
         Config: SubscriberConfig,
 {
-    fn subscribe<Callback, Error>(&mut self, event_type: Type, callback: Callback)
+    fn subscribe<Callback, Error>(
+        &mut self,
+        subscription: Subscription<Type, Event>,
+        handler: SubscriberHandler<Event, Callback>,
+    )
         where
             Error: SubscriberError,
             Sub: Subscriber<Event, Error>,
             Callback: SubscriberCallback<Event> + 'static,
     {
-        let sub = Subscriber::new(callback);
-        if let Some(store) = self.store.get_mut(&event_type) {
-            while let Some(event) = store.pop_front() {
-                if let Err(err) = Sub::notify(&sub, event) {
-                    warn!("Could not notify subscriber {:?}",err);
+        self.next_sequence += 1;
+        let order = DispatchOrder::new(subscription.get_priority(), self.next_sequence);
+        let callback = match handler {
+            SubscriberHandler::Callback(callback) => callback,
+            SubscriberHandler::Interceptor(interceptor) => {
+                let index = self.interceptors.partition_point(|(other, _, _)| *other < order);
+                self.interceptors.insert(index, (order, subscription, Interceptor(interceptor)));
+                return;
+            },
+        };
+        let entry = SubscriberEntry::new(order, CircuitBreaker::new(self.config.breaker_policy()), Subscriber::new(callback));
+        if let Some(event_type) = subscription.single_type() {
+            if let Some(store) = self.store.remove(event_type) {
+                self.metrics.stored().sub(store.len() as u64);
+                for event in store {
+                    if entry.breaker.is_closed() {
+                        self.deliver(&entry, &event, 0);
+                    }
                 }
             }
+            self.subscribers
+                .entry(event_type.clone())
+                .or_insert_with(Subscribers::default)
+                .push(entry);
+            self.count_subscribers();
+            self.drop_removed();
+            return;
+        }
+        let mut accepted = Vec::new();
+        for store in self.store.values_mut() {
+            let (matching, kept): (VecDeque<_>, _) = store.drain(..).partition(|event| subscription.matches(event));
+            *store = kept;
+            accepted.extend(matching);
+        }
+        self.metrics.stored().sub(accepted.len() as u64);
+        for event in accepted {
+            if entry.breaker.is_closed() {
+                self.deliver(&entry, &event, 0);
+            }
         }
-        self.subscribers
-            .entry(event_type)
-            .or_insert_with(Subscribers::default)
-            .push(sub);
+        let index = self.filtered.partition_point(|(_, other)| other.order < order);
+        self.filtered.insert(index, (subscription, entry));
+        self.count_subscribers();
+        self.drop_removed();
     }
 }
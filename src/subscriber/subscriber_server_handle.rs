@@ -11,6 +11,9 @@ use crate::{
         SubscriberError,
         SubscriberEvent,
         SubscriberEventType,
+        SubscriberHandler,
+        SubscriberInterceptor,
+        Subscription,
     },
 };
 use crate::subscriber::SubscriberServer;
@@ -22,25 +25,69 @@ where
     Callback: SubscriberCallback<Event>,
 {
     pub send_event:     EventHandle<Event>,
-    pub send_subscribe: SubscribeHandle<Type, Callback>,
+    pub send_subscribe: SubscribeHandle<Type, Event, Callback>,
     join_handle:        JoinHandle<()>,
 }
 
-pub struct SubscribeHandle<Type, Callback> {
-    pub send_subscribe: Sender<(Type, Callback)>,
+/// Number of events waiting for the [`SubscriberServer`] before senders have
+/// to wait
+pub const EVENT_CHANNEL_SIZE: usize = 10;
+
+/// Pending registration sent to a [`SubscriberServer`]
+pub type Subscribe<Type, Event, Callback> = (Subscription<Type, Event>, SubscriberHandler<Event, Callback>);
+
+pub struct SubscribeHandle<Type, Event, Callback> {
+    pub send_subscribe: Sender<Subscribe<Type, Event, Callback>>,
 }
 
 pub struct EventHandle<Event> {
     pub send_event: Sender<Event>,
 }
 
-impl<Type, Callback> SubscribeHandle<Type, Callback> {
+impl<Type, Event, Callback> SubscribeHandle<Type, Event, Callback>
+where
+    Type: SubscriberEventType,
+    Event: SubscriberEvent<Type = Type>,
+{
+    /// Receive the events of `event_type`
     pub async fn subscribe(
         &self,
         event_type: Type,
         callback: Callback,
-    ) -> Result<(), SendError<(Type, Callback)>> {
-        self.send_subscribe.send((event_type, callback)).await
+    ) -> Result<(), SendError<Subscribe<Type, Event, Callback>>> {
+        self.subscribe_to(Subscription::of(event_type), callback).await
+    }
+
+    /// Receive the events accepted by `subscription`, in the order given by
+    /// its priority
+    pub async fn subscribe_to(
+        &self,
+        subscription: Subscription<Type, Event>,
+        callback: Callback,
+    ) -> Result<(), SendError<Subscribe<Type, Event, Callback>>> {
+        self.send_subscribe.send((subscription, SubscriberHandler::Callback(callback))).await
+    }
+
+    /// Run `interceptor` on the events accepted by `subscription` before any
+    /// subscriber sees them, it can modify the event or return false to drop
+    /// it
+    pub async fn intercept<I>(
+        &self,
+        subscription: Subscription<Type, Event>,
+        interceptor: I,
+    ) -> Result<(), SendError<Subscribe<Type, Event, Callback>>>
+    where
+        I: SubscriberInterceptor<Event> + 'static,
+    {
+        self.send_subscribe.send((subscription, SubscriberHandler::Interceptor(Box::new(interceptor)))).await
+    }
+}
+
+impl<Event> Clone for EventHandle<Event> {
+    fn clone(&self) -> Self {
+        Self {
+            send_event: self.send_event.clone(),
+        }
     }
 }
 
@@ -48,6 +95,11 @@ impl<Event: Debug> EventHandle<Event> {
     pub async fn send(&self, event: Event) -> Result<(), SendError<Event>> {
         self.send_event.send(event).await
     }
+
+    /// Room left in the channel
+    pub fn capacity(&self) -> usize {
+        self.send_event.capacity()
+    }
 }
 
 impl<Type, Event, Callback> SubscriberServerHandle<Type, Event, Callback>
@@ -67,7 +119,7 @@ where
         Receiver<Callback>: Send + 'static,
         Receiver<Event>: Send + 'static,
     {
-        let (send_event, recv_event) = channel(10);
+        let (send_event, recv_event) = channel(EVENT_CHANNEL_SIZE);
         let (send_subscribe, recv_subscribe) = channel(10);
         let join_handle = rt.spawn(async move { server.run(recv_event, recv_subscribe).await });
         Self {
@@ -82,7 +134,7 @@ where
         self,
     ) -> (
         EventHandle<Event>,
-        SubscribeHandle<Type, Callback>,
+        SubscribeHandle<Type, Event, Callback>,
         JoinHandle<()>,
     ) {
         (self.send_event, self.send_subscribe, self.join_handle)
@@ -101,7 +153,7 @@ where
         &self,
         event_type: Type,
         callback: Callback,
-    ) -> Result<(), SendError<(Type, Callback)>> {
+    ) -> Result<(), SendError<Subscribe<Type, Event, Callback>>> {
         self.send_subscribe.subscribe(event_type, callback).await
     }
 }
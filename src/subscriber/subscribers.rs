@@ -1,9 +1,72 @@
-use std::fmt::Debug;
-use crate::subscriber::{Subscriber, SubscriberError, SubscriberEvent};
+use std::{cmp::Reverse, fmt::Debug};
+use tracing::{field, info_span, Span};
+use crate::subscriber::{CircuitBreaker, Subscriber, SubscriberError, SubscriberEvent, SubscriberId};
 
+/// Position of a subscriber in the dispatch: higher priorities first, then
+/// the order of subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DispatchOrder {
+    priority: Reverse<i32>,
+    sequence: u64,
+}
+
+impl DispatchOrder {
+    #[must_use]
+    pub fn new(priority: i32, sequence: u64) -> Self {
+        Self {
+            priority: Reverse(priority),
+            sequence,
+        }
+    }
+
+    /// The subscription order identifies the subscriber
+    #[must_use]
+    pub fn subscriber(&self) -> SubscriberId {
+        self.sequence
+    }
+}
+
+/// Span of a callback call, child of the trace of `event` if it has one
+pub(crate) fn delivery_span<Event: SubscriberEvent>(event: &Event, subscriber: SubscriberId) -> Span {
+    match event.trace() {
+        Some(trace) => {
+            let span = info_span!(
+                parent: trace.span(),
+                "deliver",
+                subscriber,
+                trace_id = field::Empty,
+                span_id = field::Empty
+            );
+            trace.child(span).span().clone()
+        },
+        None => Span::none(),
+    }
+}
+
+/// A subscriber with its place in the dispatch and its circuit breaker
+#[derive(Debug)]
+pub struct SubscriberEntry<Sub> {
+    pub order:   DispatchOrder,
+    pub breaker: CircuitBreaker,
+    pub sub:     Sub,
+}
+
+impl<Sub> SubscriberEntry<Sub> {
+    #[must_use]
+    pub fn new(order: DispatchOrder, breaker: CircuitBreaker, sub: Sub) -> Self {
+        Self { order, breaker, sub }
+    }
+
+    #[must_use]
+    pub fn id(&self) -> SubscriberId {
+        self.order.subscriber()
+    }
+}
+
+/// Subscribers kept in their [`DispatchOrder`]
 #[derive(Debug)]
 pub struct Subscribers<Sub> {
-    subscribers: Vec<Sub>,
+    subscribers: Vec<SubscriberEntry<Sub>>,
 }
 
 impl<Sub> Default for Subscribers<Sub> {
@@ -20,43 +83,32 @@ impl<Sub> Subscribers<Sub> {
         }
     }
 
-    pub fn push<Event, Error>(&mut self, subscriber: Sub)
+    pub fn push<Event, Error>(&mut self, entry: SubscriberEntry<Sub>)
     where
         Event: SubscriberEvent,
         Error: SubscriberError,
         Sub: Subscriber<Event, Error>,
     {
-        self.subscribers.push(subscriber);
+        let index = self.subscribers.partition_point(|other| other.order < entry.order);
+        self.subscribers.insert(index, entry);
     }
 
-    pub fn notify<Event, Error>(&self, event: Event) -> Option<Vec<Error>>
-    where
-        Event: SubscriberEvent,
-        Error: SubscriberError,
-        Sub: Subscriber<Event, Error> ,
-    {
-        if self.subscribers.len() == 1 {
-            match self.subscribers.first() {
-                None => {
-                    unreachable!("Not possible")
-                },
-                Some(first) => match first.notify(event) {
-                    Ok(..) => None,
-                    Err(e) => Some(vec![e]),
-                },
-            }
-        } else {
-            let mut errors = Vec::with_capacity(self.subscribers.len());
-            for sub in &self.subscribers {
-                if let Err(e) = sub.notify(event.clone()) {
-                    errors.push(e);
-                }
-            }
-            if errors.is_empty() {
-                None
-            } else {
-                Some(errors)
-            }
-        }
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SubscriberEntry<Sub>> {
+        self.subscribers.iter()
+    }
+
+    /// Drop the subscribers whose breaker removed them
+    pub fn retain_active(&mut self) {
+        self.subscribers.retain(|entry| !entry.breaker.is_removed());
     }
 }
@@ -0,0 +1,172 @@
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Formatter},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+use regex::Regex;
+
+use crate::subscriber::{SubscriberEvent, SubscriberEventType};
+
+/// Predicate evaluated by the server before an event is handed to a callback
+pub type EventFilter<Event> = Box<dyn Fn(&Event) -> bool + Send + Sync>;
+
+/// Which events a subscriber receives: every event or a set of types, further
+/// narrowed by filters which all have to accept the event. Its priority
+/// places it in the dispatch, see [`crate::subscriber::SubscriberServer`]
+pub struct Subscription<Type, Event> {
+    types:    Option<HashSet<Type>>,
+    filters:  Vec<EventFilter<Event>>,
+    priority: i32,
+}
+
+impl<Type, Event> Subscription<Type, Event>
+where
+    Type: SubscriberEventType,
+    Event: SubscriberEvent<Type = Type>,
+{
+    /// Every event
+    #[must_use]
+    pub fn all() -> Self {
+        Self {
+            types:    None,
+            filters:  Vec::new(),
+            priority: 0,
+        }
+    }
+
+    /// Events of one type
+    #[must_use]
+    pub fn of(event_type: Type) -> Self {
+        Self::types([event_type])
+    }
+
+    /// Events of any of these types
+    pub fn types<I: IntoIterator<Item = Type>>(types: I) -> Self {
+        Self {
+            types:    Some(types.into_iter().collect()),
+            filters:  Vec::new(),
+            priority: 0,
+        }
+    }
+
+    /// Subscriptions with a higher priority are dispatched first, 0 by default
+    #[must_use]
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    #[must_use]
+    pub fn get_priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Only events accepted by `filter`, it runs on the server task and
+    /// should not block nor panic
+    #[must_use]
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Only events whose payload starts with `prefix`
+    #[must_use]
+    pub fn payload_prefix<P: Into<String>>(self, prefix: P) -> Self
+    where
+        Event: 'static,
+    {
+        let prefix = prefix.into();
+        self.filter(move |event| matches!(event.payload(), Some(payload) if payload.starts_with(&prefix)))
+    }
+
+    /// Only events whose payload matches `regex`
+    #[must_use]
+    pub fn payload_regex(self, regex: Regex) -> Self
+    where
+        Event: 'static,
+    {
+        self.filter(move |event| matches!(event.payload(), Some(payload) if regex.is_match(payload)))
+    }
+
+    /// Whether `event` should be delivered to this subscription
+    pub fn matches(&self, event: &Event) -> bool {
+        let typed = match &self.types {
+            Some(types) => types.contains(&event.get_type()),
+            None => true,
+        };
+        typed && self.filters.iter().all(|filter| filter(event))
+    }
+
+    /// The type of a plain single type subscription
+    pub(crate) fn single_type(&self) -> Option<&Type> {
+        match &self.types {
+            Some(types) if types.len() == 1 && self.filters.is_empty() => types.iter().next(),
+            _ => None,
+        }
+    }
+}
+
+impl<Type: Debug, Event> Debug for Subscription<Type, Event> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription")
+            .field("types", &self.types)
+            .field("filters", &self.filters.len())
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+/// Parse a filter spec made of `;` separated clauses, an empty spec or `*`
+/// receives every event:
+/// - `type=A,B` events of one of these types
+/// - `prefix=text` payload starting with `text`
+/// - `priority=n` dispatch priority, higher first
+/// - `regex=expr` payload matching `expr`, it takes the rest of the spec so
+///   the expression can contain `;`
+impl<Type, Event> FromStr for Subscription<Type, Event>
+where
+    Type: SubscriberEventType + FromStr<Err = anyhow::Error>,
+    Event: SubscriberEvent<Type = Type> + 'static,
+{
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut subscription = Self::all();
+        let mut rest = spec.trim();
+        while !rest.is_empty() {
+            rest = rest.trim_start();
+            let clause = if rest.starts_with("regex=") {
+                std::mem::take(&mut rest)
+            } else {
+                let (clause, remaining) = rest.split_once(';').unwrap_or((rest, ""));
+                rest = remaining;
+                clause
+            };
+            let (key, value) = match clause.trim() {
+                "" | "*" => continue,
+                clause => clause.split_once('=').ok_or_else(|| anyhow!("Invalid filter clause {}", clause))?,
+            };
+            subscription = match key.trim() {
+                "type" => {
+                    let types = value.split(',').map(|t| t.trim().parse()).collect::<Result<HashSet<_>, _>>()?;
+                    match subscription.types.take() {
+                        // several type clauses narrow each other
+                        Some(previous) => subscription.types = Some(previous.intersection(&types).cloned().collect()),
+                        None => subscription.types = Some(types),
+                    }
+                    subscription
+                },
+                "prefix" => subscription.payload_prefix(value),
+                "priority" => subscription.priority(value.trim().parse()?),
+                "regex" => subscription.payload_regex(Regex::new(value)?),
+                key => return Err(anyhow!("Unknown filter clause {}", key)),
+            };
+        }
+        Ok(subscription)
+    }
+}
@@ -0,0 +1,10 @@
+#![allow(clippy::module_name_repetitions, clippy::module_inception)]
+pub use topic_event::TopicEvent;
+pub use topic_pattern::{TopicPattern, TopicSegment};
+pub use topic_server::{TopicServer, TopicServerHandle};
+pub use topic_trie::TopicTrie;
+
+mod topic_event;
+mod topic_pattern;
+mod topic_server;
+mod topic_trie;
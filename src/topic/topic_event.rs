@@ -0,0 +1,43 @@
+use anyhow::anyhow;
+
+use crate::subscriber::{SubscriberEvent, SubscriberEventType};
+
+/// Event published on a dot separated topic such as `orders.eu.created`
+#[derive(Clone, Debug)]
+pub struct TopicEvent {
+    pub topic:   String,
+    pub payload: String,
+}
+
+impl TopicEvent {
+    /// # Errors
+    /// If the topic has an empty segment or a wildcard
+    pub fn new<T: Into<String>, P: Into<String>>(topic: T, payload: P) -> anyhow::Result<Self> {
+        let topic = topic.into();
+        if topic.split('.').any(|segment| segment.is_empty() || segment == "*" || segment == ">") {
+            return Err(anyhow!("Invalid topic {}, segments should be non empty and not wildcards", topic));
+        }
+        Ok(Self {
+            topic,
+            payload: payload.into(),
+        })
+    }
+}
+
+impl SubscriberEventType for String {}
+
+impl SubscriberEvent for TopicEvent {
+    type Type = String;
+
+    fn should_kill(&self) -> bool {
+        false
+    }
+
+    fn get_type(&self) -> Self::Type {
+        self.topic.clone()
+    }
+
+    fn payload(&self) -> Option<&str> {
+        Some(&self.payload)
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use anyhow::anyhow;
+
+/// One segment of a [`TopicPattern`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TopicSegment {
+    /// Matches this exact segment
+    Literal(String),
+    /// `*` matches any single segment
+    Any,
+    /// `>` matches one or more trailing segments
+    Rest,
+}
+
+/// Subscription pattern on dot separated topics, `orders.*.created` matches
+/// `orders.eu.created` and `orders.>` matches every topic below `orders`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TopicPattern {
+    segments: Vec<TopicSegment>,
+}
+
+impl TopicPattern {
+    #[must_use]
+    pub fn segments(&self) -> &[TopicSegment] {
+        &self.segments
+    }
+}
+
+impl FromStr for TopicPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut parts = s.split('.').peekable();
+        while let Some(part) = parts.next() {
+            segments.push(match part {
+                "" => return Err(anyhow!("Invalid topic pattern {}, segments should be non empty", s)),
+                "*" => TopicSegment::Any,
+                ">" if parts.peek().is_none() => TopicSegment::Rest,
+                ">" => return Err(anyhow!("Invalid topic pattern {}, > should be the last segment", s)),
+                literal => TopicSegment::Literal(String::from(literal)),
+            });
+        }
+        Ok(Self { segments })
+    }
+}
+
+impl Display for TopicPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            match segment {
+                TopicSegment::Literal(literal) => write!(f, "{}", literal)?,
+                TopicSegment::Any => write!(f, "*")?,
+                TopicSegment::Rest => write!(f, ">")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use tokio::{
+    sync::mpsc::{channel, error::SendError, Receiver, Sender},
+    task::JoinHandle,
+};
+use tracing::warn;
+
+use crate::{
+    subscriber::{Subscriber, SubscriberCallback, SubscriberError},
+    topic::{TopicEvent, TopicPattern, TopicTrie},
+};
+
+/// Topic based counterpart of [`crate::subscriber::SubscriberServer`], events
+/// are delivered to every subscriber whose pattern matches their topic in
+/// subscription order
+#[derive(Debug)]
+pub struct TopicServer<Sub> {
+    subscribers: TopicTrie<(u64, Sub)>,
+    next_id:     u64,
+}
+
+impl<Sub> Default for TopicServer<Sub> {
+    fn default() -> Self {
+        Self {
+            subscribers: TopicTrie::default(),
+            next_id:     0,
+        }
+    }
+}
+
+impl<Sub> TopicServer<Sub> {
+    pub async fn run<Error, Callback>(
+        mut self,
+        mut recv_publish: Receiver<TopicEvent>,
+        mut recv_subscribe: Receiver<(TopicPattern, Callback)>,
+    ) where
+        Error: SubscriberError,
+        Sub: Subscriber<TopicEvent, Error>,
+        Callback: SubscriberCallback<TopicEvent> + 'static,
+    {
+        loop {
+            tokio::select! {
+                Some((pattern, callback)) = recv_subscribe.recv() => {
+                    self.next_id += 1;
+                    self.subscribers.insert(&pattern, (self.next_id, Sub::new(callback)));
+                }
+                Some(event) = recv_publish.recv() => {
+                    self.publish(event);
+                }
+                else => {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn publish<Error>(&self, event: TopicEvent)
+    where
+        Error: SubscriberError,
+        Sub: Subscriber<TopicEvent, Error>,
+    {
+        let mut subscribers = self.subscribers.matches(&event.topic);
+        subscribers.sort_unstable_by_key(|(id, _)| *id);
+        for (_, sub) in subscribers {
+            if let Err(err) = sub.notify(event.clone()) {
+                warn!("Could not notify topic subscriber {:?}", err);
+            }
+        }
+    }
+}
+
+pub struct TopicServerHandle<Callback> {
+    send_publish:   Sender<TopicEvent>,
+    send_subscribe: Sender<(TopicPattern, Callback)>,
+    join_handle:    JoinHandle<()>,
+}
+
+impl<Callback> TopicServerHandle<Callback>
+where
+    Callback: SubscriberCallback<TopicEvent> + 'static,
+{
+    pub fn new<Sub, Error>(server: TopicServer<Sub>, rt: &tokio::runtime::Handle) -> Self
+    where
+        Error: SubscriberError,
+        Sub: Subscriber<TopicEvent, Error> + 'static,
+    {
+        let (send_publish, recv_publish) = channel(1024);
+        let (send_subscribe, recv_subscribe) = channel(10);
+        let join_handle = rt.spawn(async move { server.run(recv_publish, recv_subscribe).await });
+        Self {
+            send_publish,
+            send_subscribe,
+            join_handle,
+        }
+    }
+
+    pub async fn publish(&self, event: TopicEvent) -> Result<(), SendError<TopicEvent>> {
+        self.send_publish.send(event).await
+    }
+
+    /// Receive the events whose topic matches `pattern`
+    pub async fn subscribe(
+        &self,
+        pattern: TopicPattern,
+        callback: Callback,
+    ) -> Result<(), SendError<(TopicPattern, Callback)>> {
+        self.send_subscribe.send((pattern, callback)).await
+    }
+
+    /// This does not wait for the pending events to be delivered
+    pub fn stop_handle(&self) {
+        self.join_handle.abort();
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use crate::topic::{TopicPattern, TopicSegment};
+
+/// Values stored by [`TopicPattern`], looked up by concrete topic without
+/// checking every pattern
+#[derive(Debug)]
+pub struct TopicTrie<T> {
+    root: Node<T>,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    literals: HashMap<String, Node<T>>,
+    any:      Option<Box<Node<T>>>,
+    // values of patterns ending with `>` at this level
+    rest:     Vec<T>,
+    // values of patterns ending exactly at this level
+    values:   Vec<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            literals: HashMap::new(),
+            any:      None,
+            rest:     Vec::new(),
+            values:   Vec::new(),
+        }
+    }
+}
+
+impl<T> Default for TopicTrie<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+        }
+    }
+}
+
+impl<T> TopicTrie<T> {
+    pub fn insert(&mut self, pattern: &TopicPattern, value: T) {
+        let mut node = &mut self.root;
+        for segment in pattern.segments() {
+            node = match segment {
+                TopicSegment::Literal(literal) => node.literals.entry(literal.clone()).or_default(),
+                TopicSegment::Any => node.any.get_or_insert_with(Box::default),
+                TopicSegment::Rest => {
+                    node.rest.push(value);
+                    return;
+                },
+            };
+        }
+        node.values.push(value);
+    }
+
+    /// Values of every pattern matching `topic`
+    pub fn matches(&self, topic: &str) -> Vec<&T> {
+        let segments: Vec<&str> = topic.split('.').collect();
+        let mut found = Vec::new();
+        Self::collect(&self.root, &segments, &mut found);
+        found
+    }
+
+    fn collect<'a>(node: &'a Node<T>, segments: &[&str], found: &mut Vec<&'a T>) {
+        let (first, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => {
+                found.extend(&node.values);
+                return;
+            },
+        };
+        found.extend(&node.rest);
+        if let Some(child) = node.literals.get(*first) {
+            Self::collect(child, rest, found);
+        }
+        if let Some(child) = &node.any {
+            Self::collect(child, rest, found);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#![allow(clippy::module_name_repetitions)]
+pub use trace_context::TraceContext;
+
+mod trace_context;
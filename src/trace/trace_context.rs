@@ -0,0 +1,105 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::{Debug, Formatter},
+    hash::{BuildHasher, Hasher},
+    panic::{RefUnwindSafe, UnwindSafe},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tracing::{field, Span};
+
+/// Position of a message or an event in a trace, following the W3C trace
+/// context model: every hop gets a new span id under the same trace id. The
+/// `tracing` span of the hop is kept so the next one can be created as its
+/// child and entered from another task.
+///
+/// Spans declaring empty `trace_id` and `span_id` fields get them recorded.
+#[derive(Clone)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id:  u64,
+    span:     Span,
+}
+
+impl TraceContext {
+    /// Start a new trace whose first hop is `span`
+    #[must_use]
+    pub fn new(span: Span) -> Self {
+        let trace_id = u128::from(random_id()) << 64 | u128::from(random_id());
+        Self::with_ids(trace_id, span)
+    }
+
+    /// Next hop of the same trace, `span` is usually created with
+    /// [`TraceContext::span`] as parent
+    #[must_use]
+    pub fn child(&self, span: Span) -> Self {
+        Self::with_ids(self.trace_id, span)
+    }
+
+    fn with_ids(trace_id: u128, span: Span) -> Self {
+        let span_id = random_id();
+        span.record(
+            "trace_id",
+            &field::display(format_args!("{:032x}", trace_id)),
+        );
+        span.record("span_id", &field::display(format_args!("{:016x}", span_id)));
+        Self {
+            trace_id,
+            span_id,
+            span,
+        }
+    }
+
+    #[must_use]
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    #[must_use]
+    pub fn span_id(&self) -> u64 {
+        self.span_id
+    }
+
+    /// Span of this hop, disabled when no subscriber is interested in it
+    #[must_use]
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Value of the W3C `traceparent` header for this hop
+    #[must_use]
+    pub fn traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+}
+
+impl Debug for TraceContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TraceContext").field(&self.traceparent()).finish()
+    }
+}
+
+impl PartialEq for TraceContext {
+    fn eq(&self, other: &Self) -> bool {
+        self.trace_id == other.trace_id && self.span_id == other.span_id
+    }
+}
+
+impl Eq for TraceContext {
+}
+
+// the span is a handle to the tracing subscriber which stays usable after a
+// callback panicked
+impl UnwindSafe for TraceContext {
+}
+impl RefUnwindSafe for TraceContext {
+}
+
+/// Non zero identifier, random enough to tell traces apart without pulling
+/// a random number generator
+fn random_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish().max(1)
+}
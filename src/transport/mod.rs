@@ -0,0 +1,20 @@
+#![allow(clippy::module_name_repetitions, clippy::module_inception)]
+pub use transport_backoff::Backoff;
+pub use transport_client::{Connector, StreamClient};
+pub use transport_config::TransportConfig;
+pub use transport_event::ClientEvent;
+pub use transport_framing::Framing;
+pub use transport_process::ProcessClient;
+pub use transport_tcp::TcpConnector;
+pub use transport_unix::UnixConnector;
+pub use transport_websocket::WebSocketClient;
+
+mod transport_backoff;
+mod transport_client;
+mod transport_config;
+mod transport_event;
+mod transport_framing;
+mod transport_process;
+mod transport_tcp;
+mod transport_unix;
+mod transport_websocket;
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+/// Shortest delay between two attempts, a zero delay would retry in a hot
+/// loop against a dead peer
+const MIN_DELAY: Duration = Duration::from_millis(1);
+
+/// Exponential backoff used between two reconnection attempts
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max:     Duration,
+    factor:  u32,
+    current: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100), Duration::from_secs(30), 2)
+    }
+}
+
+impl Backoff {
+    /// Delays are at least 1ms, `initial` included, and `max` is never
+    /// shorter than `initial`
+    #[must_use]
+    pub fn new(initial: Duration, max: Duration, factor: u32) -> Self {
+        let initial = initial.max(MIN_DELAY);
+        let max = max.max(initial);
+        Self {
+            initial,
+            max,
+            factor: factor.max(1),
+            current: initial,
+        }
+    }
+
+    /// Returns the delay to wait before the next attempt and grows the
+    /// following one up to the maximum
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = self.current.saturating_mul(self.factor).min(self.max);
+        delay
+    }
+
+    /// Start again from the initial delay, to be called once connected
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
@@ -0,0 +1,205 @@
+use std::{collections::VecDeque, future::Future, pin::Pin, time::Duration};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader},
+    sync::broadcast::{self, error::RecvError},
+};
+use tracing::{info, warn};
+
+use crate::{
+    transport::{Backoff, ClientEvent, TransportConfig},
+    Message,
+};
+
+/// Maximum number of messages kept while the peer is not reachable, the
+/// oldest ones are dropped first
+const PENDING_CAPACITY: usize = 1024;
+
+/// Open a byte stream to a peer, the stream is then framed by a
+/// [`StreamClient`]
+pub trait Connector: Send + Sync + 'static {
+    type Stream: AsyncRead + AsyncWrite + Send + Unpin + 'static;
+
+    /// Human readable address of the peer, used in logs and [`ClientEvent`]
+    fn peer(&self) -> String;
+
+    /// Open a new stream to the peer
+    ///
+    /// # Errors
+    /// If the peer can not be reached
+    fn connect(&self) -> Pin<Box<dyn Future<Output = std::io::Result<Self::Stream>> + Send + '_>>;
+}
+
+/// Client backend exchanging framed [`Message`] over a stream and
+/// reconnecting with a [`Backoff`] whenever the stream is lost
+#[derive(Debug)]
+pub struct StreamClient<C> {
+    connector: C,
+    config:    TransportConfig,
+    backoff:   Backoff,
+    sender:    broadcast::Sender<Message>,
+    receiver:  broadcast::Receiver<Message>,
+    events:    broadcast::Sender<ClientEvent>,
+    pending:   VecDeque<Message>,
+}
+
+impl<C: Connector> StreamClient<C> {
+    pub fn new(
+        connector: C,
+        config: TransportConfig,
+        sender: broadcast::Sender<Message>,
+        receiver: broadcast::Receiver<Message>,
+        events: broadcast::Sender<ClientEvent>,
+    ) -> Self {
+        let backoff = config.backoff.clone();
+        Self {
+            connector,
+            config,
+            backoff,
+            sender,
+            receiver,
+            events,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Run until the [`crate::ClientHandle`] is dropped
+    pub(crate) async fn run(mut self) {
+        let peer = self.connector.peer();
+        loop {
+            match with_timeout(self.config.connect_timeout, self.connector.connect()).await {
+                Ok(stream) => {
+                    info!("Connected to {}", peer);
+                    self.backoff.reset();
+                    self.notify(ClientEvent::Connected(peer.clone()));
+                    let open = self.serve(stream).await;
+                    self.notify(ClientEvent::Disconnected(peer.clone()));
+                    if !open {
+                        return;
+                    }
+                },
+                Err(err) => {
+                    warn!("Could not connect to {}: {}", peer, err);
+                },
+            }
+            let delay = self.backoff.next_delay();
+            self.notify(ClientEvent::Reconnecting(peer.clone()));
+            if !wait_for_retry(&mut self.receiver, &mut self.pending, delay).await {
+                return;
+            }
+        }
+    }
+
+    fn notify(&self, event: ClientEvent) {
+        // nobody listening to the lifecycle is not an error
+        let _ = self.events.send(event);
+    }
+
+    /// Exchange messages until the stream fails, returns false if the client
+    /// handle was dropped
+    async fn serve(&mut self, stream: C::Stream) -> bool {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        let TransportConfig {
+            framing,
+            read_timeout,
+            write_timeout,
+            ..
+        } = self.config;
+        let sender = &self.sender;
+        let receiver = &mut self.receiver;
+        let pending = &mut self.pending;
+
+        let read_loop = async {
+            loop {
+                match with_timeout(read_timeout, framing.read(&mut reader)).await {
+                    Ok(msg) => {
+                        if let Err(err) = sender.send(msg) {
+                            warn!("{:?}", err);
+                        }
+                    },
+                    Err(err) => return err,
+                }
+            }
+        };
+
+        // a message stays pending until fully written, so it is sent again
+        // on the next connection if this loop is dropped mid write
+        let write_loop = async {
+            loop {
+                while let Some(msg) = pending.front() {
+                    // sending it again would fail the same way on every
+                    // connection and hold the messages queued behind it
+                    if let Err(err) = framing.check(msg) {
+                        warn!("Dropping message which can not be framed: {}", err);
+                        pending.pop_front();
+                        continue;
+                    }
+                    if let Err(err) = with_timeout(write_timeout, framing.write(&mut writer, msg)).await {
+                        warn!("Could not write message: {}", err);
+                        return true;
+                    }
+                    pending.pop_front();
+                }
+                match receiver.recv().await {
+                    Ok(msg) => queue(pending, msg),
+                    Err(RecvError::Lagged(count)) => warn!("Client lagged by {} messages", count),
+                    Err(RecvError::Closed) => return false,
+                }
+            }
+        };
+
+        tokio::select! {
+            err = read_loop => {
+                warn!("Connection lost: {}", err);
+                true
+            }
+            open = write_loop => open,
+        }
+    }
+}
+
+/// Keep a message to be sent once connected again
+pub(crate) fn queue(pending: &mut VecDeque<Message>, msg: Message) {
+    if pending.len() >= PENDING_CAPACITY {
+        warn!("Dropping pending message, peer unreachable for too long");
+        pending.pop_front();
+    }
+    pending.push_back(msg);
+}
+
+/// Wait before the next attempt while keeping the outgoing messages,
+/// returns false if the client handle was dropped
+pub(crate) async fn wait_for_retry(
+    receiver: &mut broadcast::Receiver<Message>,
+    pending: &mut VecDeque<Message>,
+    delay: Duration,
+) -> bool {
+    let sleep = tokio::time::sleep(delay);
+    tokio::pin!(sleep);
+    loop {
+        tokio::select! {
+            _ = &mut sleep => return true,
+            msg = receiver.recv() => match msg {
+                Ok(msg) => queue(pending, msg),
+                Err(RecvError::Lagged(count)) => warn!("Client lagged by {} messages", count),
+                Err(RecvError::Closed) => return false,
+            },
+        }
+    }
+}
+
+/// Fail with [`std::io::ErrorKind::TimedOut`] if the future did not complete
+/// within `timeout`
+pub(crate) async fn with_timeout<T, F>(timeout: Option<Duration>, future: F) -> std::io::Result<T>
+where
+    F: Future<Output = std::io::Result<T>>,
+{
+    match timeout {
+        None => future.await,
+        Some(timeout) => match tokio::time::timeout(timeout, future).await {
+            Ok(res) => res,
+            Err(_) => Err(std::io::ErrorKind::TimedOut.into()),
+        },
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use crate::transport::{Backoff, Framing};
+
+/// Settings shared by the stream based client backends
+#[derive(Debug, Clone)]
+pub struct TransportConfig {
+    /// How messages are delimited on the stream
+    pub framing:         Framing,
+    /// Give up a connection attempt after this delay
+    pub connect_timeout: Option<Duration>,
+    /// Consider the connection lost if nothing was received for this delay,
+    /// an idle timeout so the peer has to send something more often than
+    /// that, such as heartbeats. None keeps a silent connection open
+    pub read_timeout:    Option<Duration>,
+    /// Consider the connection lost if a message could not be written in
+    /// this delay
+    pub write_timeout:   Option<Duration>,
+    /// Idle time before TCP keepalive probes are sent, ignored for Unix
+    /// sockets
+    pub keepalive:       Option<Duration>,
+    /// Delay between two connection attempts
+    pub backoff:         Backoff,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            framing:         Framing::default(),
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout:    None,
+            write_timeout:   Some(Duration::from_secs(10)),
+            keepalive:       Some(Duration::from_secs(60)),
+            backoff:         Backoff::default(),
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/// Events emitted by a client backend next to the messages it receives, for
+/// the connection lifecycle the peer is given as a human readable address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientEvent {
+    /// The backend is connected to the peer
+    Connected(String),
+    /// The connection to the peer was lost
+    Disconnected(String),
+    /// The backend is waiting before trying to connect again
+    Reconnecting(String),
+    /// A line written by a child process on its stdout
+    Stdout(String),
+    /// A line written by a child process on its stderr
+    Stderr(String),
+    /// The child process exited with this code, or minus the signal number
+    /// that killed it
+    Exited(i32),
+}
@@ -0,0 +1,108 @@
+use crate::Message;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Frames bigger than this are considered as a corrupted stream
+const MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// How [`Message`] are delimited on a byte stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Each message is prefixed by its length as a big endian `u32`
+    LengthDelimited,
+    /// Each message is terminated by `\n`, a trailing `\r` is stripped
+    Newline,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::LengthDelimited
+    }
+}
+
+impl Framing {
+    /// Read the next message from the stream
+    ///
+    /// # Errors
+    /// If the stream is closed, the frame is too big or not valid UTF-8
+    pub(crate) async fn read<R>(self, reader: &mut R) -> std::io::Result<Message>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let buffer = match self {
+            Framing::LengthDelimited => {
+                let len = reader.read_u32().await? as usize;
+                check_length(len)?;
+                let mut buffer = vec![0; len];
+                reader.read_exact(&mut buffer).await?;
+                buffer
+            },
+            Framing::Newline => {
+                let mut buffer = Vec::new();
+                let mut limited = reader.take(MAX_FRAME_LENGTH as u64 + 1);
+                if limited.read_until(b'\n', &mut buffer).await? == 0 {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                if buffer.last() != Some(&b'\n') {
+                    check_length(buffer.len())?;
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                buffer.pop();
+                if buffer.last() == Some(&b'\r') {
+                    buffer.pop();
+                }
+                buffer
+            },
+        };
+        let message = String::from_utf8(buffer)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(Message::new(message))
+    }
+
+    /// Check that a message can be framed, before writing any of it
+    ///
+    /// # Errors
+    /// If the message is too big or holds a `\n` with [`Framing::Newline`]
+    pub(crate) fn check(self, message: &Message) -> std::io::Result<()> {
+        check_length(message.message.len())?;
+        if self == Framing::Newline && message.message.contains('\n') {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Message contains a newline and can not be framed",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write a message to the stream and flush it
+    ///
+    /// # Errors
+    /// If the stream is closed or the message can not be framed
+    pub(crate) async fn write<W>(self, writer: &mut W, message: &Message) -> std::io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.check(message)?;
+        let bytes = message.message.as_bytes();
+        match self {
+            Framing::LengthDelimited => {
+                writer.write_u32(bytes.len() as u32).await?;
+                writer.write_all(bytes).await?;
+            },
+            Framing::Newline => {
+                writer.write_all(bytes).await?;
+                writer.write_all(b"\n").await?;
+            },
+        }
+        writer.flush().await
+    }
+}
+
+fn check_length(len: usize) -> std::io::Result<()> {
+    if len > MAX_FRAME_LENGTH {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Frame of {} bytes exceeds the maximum length", len),
+        ));
+    }
+    Ok(())
+}
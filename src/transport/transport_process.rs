@@ -0,0 +1,144 @@
+use std::{os::unix::process::ExitStatusExt, process::Stdio};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{ChildStdin, Command},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+};
+use tracing::{info, warn};
+
+use crate::{transport::ClientEvent, Message};
+
+/// Client backend running a child process, each line of its stdout and
+/// stderr is emitted as a [`ClientEvent`] and every message sent to the
+/// client is written to its stdin followed by a newline.
+///
+/// The process is killed once the [`crate::ClientHandle`] is dropped, in every
+/// case a [`ClientEvent::Exited`] is emitted last.
+#[derive(Debug)]
+pub struct ProcessClient {
+    command:  String,
+    args:     Vec<String>,
+    receiver: broadcast::Receiver<Message>,
+    events:   broadcast::Sender<ClientEvent>,
+}
+
+impl ProcessClient {
+    pub fn new<C: Into<String>>(
+        command: C,
+        args: Vec<String>,
+        receiver: broadcast::Receiver<Message>,
+        events: broadcast::Sender<ClientEvent>,
+    ) -> Self {
+        Self {
+            command: command.into(),
+            args,
+            receiver,
+            events,
+        }
+    }
+
+    /// Run until the process exited
+    pub(crate) async fn run(mut self) {
+        let mut child = match Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                warn!("Could not spawn {}: {}", self.command, err);
+                self.notify(ClientEvent::Exited(-1));
+                return;
+            },
+        };
+        info!("Spawned {} with pid {:?}", self.command, child.id());
+
+        // written by its own task so the output keeps being read while the
+        // child is slow to read its input
+        let stdin = child.stdin.take().map(|stdin| {
+            let (input, lines) = mpsc::unbounded_channel();
+            tokio::spawn(write_lines(stdin, lines));
+            input
+        });
+        let mut stdout = child.stdout.take().map(|out| BufReader::new(out).lines());
+        let mut stderr = child.stderr.take().map(|err| BufReader::new(err).lines());
+        let mut input_open = true;
+
+        let code = loop {
+            tokio::select! {
+                line = next_line(&mut stdout), if stdout.is_some() => match line {
+                    Some(line) => self.notify(ClientEvent::Stdout(line)),
+                    None => stdout = None,
+                },
+                line = next_line(&mut stderr), if stderr.is_some() => match line {
+                    Some(line) => self.notify(ClientEvent::Stderr(line)),
+                    None => stderr = None,
+                },
+                msg = self.receiver.recv(), if input_open => match msg {
+                    Ok(msg) => match &stdin {
+                        Some(input) if input.send(msg).is_ok() => {},
+                        _ => warn!("Process stdin is closed, dropping message"),
+                    },
+                    Err(RecvError::Lagged(count)) => warn!("Process input lagged by {} messages", count),
+                    Err(RecvError::Closed) => {
+                        input_open = false;
+                        if let Err(err) = child.start_kill() {
+                            warn!("Could not kill {}: {}", self.command, err);
+                        }
+                    },
+                },
+                status = child.wait(), if stdout.is_none() && stderr.is_none() => match status {
+                    Ok(status) => break status.code().or_else(|| status.signal().map(|s| -s)).unwrap_or(-1),
+                    Err(err) => {
+                        warn!("Could not wait for {}: {}", self.command, err);
+                        break -1;
+                    },
+                },
+            }
+        };
+        info!("{} exited with {}", self.command, code);
+        self.notify(ClientEvent::Exited(code));
+    }
+
+    fn notify(&self, event: ClientEvent) {
+        // nobody listening to the output is not an error
+        let _ = self.events.send(event);
+    }
+}
+
+async fn next_line<R>(lines: &mut Option<tokio::io::Lines<R>>) -> Option<String>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    match lines.as_mut()?.next_line().await {
+        Ok(line) => line,
+        Err(err) => {
+            warn!("Could not read process output: {}", err);
+            None
+        },
+    }
+}
+
+/// Write each message as a line to the process stdin until the client stops
+/// or the process closes it
+async fn write_lines(mut stdin: ChildStdin, mut lines: mpsc::UnboundedReceiver<Message>) {
+    while let Some(msg) = lines.recv().await {
+        let mut line = msg.message.into_bytes();
+        line.push(b'\n');
+        let res = async {
+            stdin.write_all(&line).await?;
+            stdin.flush().await
+        };
+        if let Err(err) = res.await {
+            warn!("Could not write to process stdin: {}", err);
+            return;
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use socket2::{SockRef, TcpKeepalive};
+use tokio::net::{lookup_host, TcpSocket, TcpStream};
+use tracing::debug;
+
+use crate::transport::Connector;
+
+/// Connect to a TCP peer given as `host:port`
+#[derive(Debug, Clone)]
+pub struct TcpConnector {
+    addr:      String,
+    keepalive: Option<Duration>,
+}
+
+impl TcpConnector {
+    pub fn new<A: Into<String>>(addr: A, keepalive: Option<Duration>) -> Self {
+        Self {
+            addr: addr.into(),
+            keepalive,
+        }
+    }
+
+    async fn connect_any(&self) -> std::io::Result<TcpStream> {
+        let mut last_error = None;
+        for addr in lookup_host(&self.addr).await? {
+            let socket = if addr.is_ipv4() {
+                TcpSocket::new_v4()?
+            } else {
+                TcpSocket::new_v6()?
+            };
+            match socket.connect(addr).await {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    if let Some(time) = self.keepalive {
+                        SockRef::from(&stream).set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+                    }
+                    return Ok(stream);
+                },
+                Err(err) => {
+                    debug!("Could not connect to {}: {}", addr, err);
+                    last_error = Some(err);
+                },
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} did not resolve to any address", self.addr),
+            )
+        }))
+    }
+}
+
+impl Connector for TcpConnector {
+    type Stream = TcpStream;
+
+    fn peer(&self) -> String {
+        format!("tcp:{}", self.addr)
+    }
+
+    fn connect(&self) -> Pin<Box<dyn Future<Output = std::io::Result<Self::Stream>> + Send + '_>> {
+        Box::pin(self.connect_any())
+    }
+}
@@ -0,0 +1,29 @@
+use std::{future::Future, path::PathBuf, pin::Pin};
+
+use tokio::net::UnixStream;
+
+use crate::transport::Connector;
+
+/// Connect to a local Unix domain socket
+#[derive(Debug, Clone)]
+pub struct UnixConnector {
+    path: PathBuf,
+}
+
+impl UnixConnector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Connector for UnixConnector {
+    type Stream = UnixStream;
+
+    fn peer(&self) -> String {
+        format!("unix:{}", self.path.display())
+    }
+
+    fn connect(&self) -> Pin<Box<dyn Future<Output = std::io::Result<Self::Stream>> + Send + '_>> {
+        Box::pin(UnixStream::connect(&self.path))
+    }
+}
@@ -0,0 +1,207 @@
+use std::{collections::VecDeque, time::Duration};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::{
+    net::TcpStream,
+    sync::broadcast::{self, error::RecvError},
+};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{self, Message as WsMessage},
+    MaybeTlsStream,
+    WebSocketStream,
+};
+use tracing::{debug, info, trace, warn};
+
+use crate::{
+    transport::{
+        transport_client::{queue, wait_for_retry, with_timeout},
+        Backoff,
+        ClientEvent,
+        TransportConfig,
+    },
+    Message,
+};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Client backend consuming a WebSocket feed, text and binary frames are
+/// turned into [`Message`] and outgoing messages are sent as text frames.
+/// `wss://` URLs are served over TLS, the server certificate is checked
+/// against the Mozilla root certificates.
+///
+/// Pings from the server are answered automatically, when
+/// [`TransportConfig::keepalive`] is set a ping is also sent at that interval.
+/// The framing of the [`TransportConfig`] is not used.
+#[derive(Debug)]
+pub struct WebSocketClient {
+    url:      String,
+    config:   TransportConfig,
+    backoff:  Backoff,
+    sender:   broadcast::Sender<Message>,
+    receiver: broadcast::Receiver<Message>,
+    events:   broadcast::Sender<ClientEvent>,
+    pending:  VecDeque<Message>,
+}
+
+impl WebSocketClient {
+    pub fn new<U: Into<String>>(
+        url: U,
+        config: TransportConfig,
+        sender: broadcast::Sender<Message>,
+        receiver: broadcast::Receiver<Message>,
+        events: broadcast::Sender<ClientEvent>,
+    ) -> Self {
+        let backoff = config.backoff.clone();
+        Self {
+            url: url.into(),
+            config,
+            backoff,
+            sender,
+            receiver,
+            events,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Run until the [`crate::ClientHandle`] is dropped
+    pub(crate) async fn run(mut self) {
+        let peer = self.url.clone();
+        loop {
+            let connect = async {
+                connect_async(self.url.as_str())
+                    .await
+                    .map(|(stream, _response)| stream)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+            };
+            match with_timeout(self.config.connect_timeout, connect).await {
+                Ok(stream) => {
+                    info!("Connected to {}", peer);
+                    self.backoff.reset();
+                    self.notify(ClientEvent::Connected(peer.clone()));
+                    let open = self.serve(stream).await;
+                    self.notify(ClientEvent::Disconnected(peer.clone()));
+                    if !open {
+                        return;
+                    }
+                },
+                Err(err) => {
+                    warn!("Could not connect to {}: {}", peer, err);
+                },
+            }
+            let delay = self.backoff.next_delay();
+            self.notify(ClientEvent::Reconnecting(peer.clone()));
+            if !wait_for_retry(&mut self.receiver, &mut self.pending, delay).await {
+                return;
+            }
+        }
+    }
+
+    fn notify(&self, event: ClientEvent) {
+        // nobody listening to the lifecycle is not an error
+        let _ = self.events.send(event);
+    }
+
+    /// Exchange messages until the connection is closed or fails, returns
+    /// false if the client handle was dropped
+    async fn serve(&mut self, stream: WsStream) -> bool {
+        let (mut sink, mut stream) = stream.split();
+        let TransportConfig {
+            read_timeout,
+            write_timeout,
+            keepalive,
+            ..
+        } = self.config;
+        let sender = &self.sender;
+        let receiver = &mut self.receiver;
+        let pending = &mut self.pending;
+
+        let read_loop = async {
+            loop {
+                let frame = match with_timeout(read_timeout, async { Ok(stream.next().await) }).await {
+                    Ok(Some(Ok(frame))) => frame,
+                    Ok(Some(Err(err))) => return err.to_string(),
+                    Ok(None) => return String::from("stream ended"),
+                    Err(err) => return err.to_string(),
+                };
+                let message = match frame {
+                    WsMessage::Text(text) => text,
+                    WsMessage::Binary(bytes) => match String::from_utf8(bytes) {
+                        Ok(text) => text,
+                        Err(err) => {
+                            debug!("Binary frame is not valid UTF-8, converting it lossily");
+                            String::from_utf8_lossy(err.as_bytes()).into_owned()
+                        },
+                    },
+                    WsMessage::Ping(_) | WsMessage::Pong(_) | WsMessage::Frame(_) => {
+                        // pongs are queued by tungstenite and flushed on the next read
+                        trace!("Received control frame");
+                        continue;
+                    },
+                    WsMessage::Close(frame) => return format!("closed by peer {:?}", frame),
+                };
+                if let Err(err) = sender.send(Message::new(message)) {
+                    warn!("{:?}", err);
+                }
+            }
+        };
+
+        // a message stays pending until fully sent, so it is sent again on
+        // the next connection if this loop is dropped mid write
+        let write_loop = async {
+            let mut ping = keepalive.map(tokio::time::interval);
+            loop {
+                while let Some(msg) = pending.front() {
+                    if let Err(err) = send(&mut sink, write_timeout, WsMessage::Text(msg.message.clone())).await {
+                        warn!("Could not write message: {}", err);
+                        return true;
+                    }
+                    pending.pop_front();
+                }
+                let tick = async {
+                    match ping.as_mut() {
+                        Some(ping) => ping.tick().await,
+                        None => std::future::pending().await,
+                    }
+                };
+                tokio::select! {
+                    msg = receiver.recv() => match msg {
+                        Ok(msg) => queue(pending, msg),
+                        Err(RecvError::Lagged(count)) => warn!("Client lagged by {} messages", count),
+                        Err(RecvError::Closed) => {
+                            let _ = send(&mut sink, write_timeout, WsMessage::Close(None)).await;
+                            return false;
+                        },
+                    },
+                    _ = tick => {
+                        if let Err(err) = send(&mut sink, write_timeout, WsMessage::Ping(Vec::new())).await {
+                            warn!("Could not send ping: {}", err);
+                            return true;
+                        }
+                    },
+                }
+            }
+        };
+
+        tokio::select! {
+            reason = read_loop => {
+                warn!("Connection lost: {}", reason);
+                true
+            }
+            open = write_loop => open,
+        }
+    }
+}
+
+/// Send a frame and flush it, failing after `timeout`
+async fn send<S>(sink: &mut S, timeout: Option<Duration>, msg: WsMessage) -> std::io::Result<()>
+where
+    S: futures_util::Sink<WsMessage, Error = tungstenite::Error> + Unpin,
+{
+    let send = async {
+        sink.send(msg)
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    };
+    with_timeout(timeout, send).await
+}
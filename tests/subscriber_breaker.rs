@@ -0,0 +1,78 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rust_py::{subscriber::BreakerPolicy, Runner, SEnvelope, SEvent, SEventType};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    time::timeout,
+};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Runner whose only Event1 subscriber panics on every call, along with how
+/// many times it was called and the suspensions it caused
+async fn failing_subscriber(
+    policy: Option<BreakerPolicy>,
+    events: usize,
+) -> (Runner, Arc<AtomicU32>, UnboundedReceiver<String>) {
+    let (_client, mut runner, subscribe) =
+        Runner::new(&tokio::runtime::Handle::current()).expect("runner");
+    if let Some(policy) = policy {
+        runner.set_breaker_policy(policy);
+    }
+    let calls = Arc::new(AtomicU32::new(0));
+    let counted = calls.clone();
+    subscribe
+        .subscribe(
+            SEventType::EventType1,
+            Box::new(move |_: SEnvelope| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                panic!("always failing");
+            }),
+        )
+        .await
+        .expect("subscribe");
+    let (suspended_tx, suspended) = mpsc::unbounded_channel();
+    let suspended_tx = AssertUnwindSafe(suspended_tx);
+    subscribe
+        .subscribe(
+            SEventType::EventTypeSubscriberSuspended,
+            Box::new(move |envelope: SEnvelope| {
+                if let SEvent::SubscriberSuspended(suspension) = envelope.event {
+                    let _ = suspended_tx.send(suspension);
+                }
+            }),
+        )
+        .await
+        .expect("subscribe");
+    let (send, receive) = mpsc::channel(events);
+    runner.add_events(receive);
+    for index in 0..events {
+        send.send(SEvent::Event1(index.to_string())).await.expect("send");
+    }
+    (runner, calls, suspended)
+}
+
+#[tokio::test]
+async fn failing_subscriber_is_suspended_by_default() {
+    let (_runner, calls, mut suspended) = failing_subscriber(None, 8).await;
+    let suspension = timeout(WAIT, suspended.recv()).await.expect("suspension in time").expect("suspension");
+    assert!(suspension.contains("failures=5 cooldown_ms=30000"), "{}", suspension);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn failing_subscriber_is_removed_without_cooldown() {
+    let (_runner, calls, mut suspended) = failing_subscriber(Some(BreakerPolicy::new(2, None)), 4).await;
+    let suspension = timeout(WAIT, suspended.recv()).await.expect("suspension in time").expect("suspension");
+    assert!(suspension.ends_with("failures=2 removed"), "{}", suspension);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
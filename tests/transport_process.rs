@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use rust_py::{transport::ClientEvent, ClientHandle, Message};
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn process_output_is_read_while_its_input_is_written() {
+    let (mut client, _, mut events) =
+        ClientHandle::process(&tokio::runtime::Handle::current(), "cat", Vec::new())
+            .expect("client");
+    // far more than a pipe holds, cat blocks on its stdout unless it is read
+    let line = "x".repeat(64 * 1024);
+    let count = 32;
+    for _ in 0..count {
+        client.send_msg(Message::new(line.clone())).expect("send");
+    }
+    let mut echoed = 0;
+    while echoed < count {
+        match timeout(Duration::from_secs(10), events.recv()).await {
+            Ok(Ok(ClientEvent::Stdout(out))) => {
+                assert_eq!(out.len(), line.len());
+                echoed += 1;
+            },
+            Ok(Ok(_)) => {},
+            other => panic!("expected the echoed lines, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use rust_py::{
+    transport::{Backoff, ClientEvent, Framing, TransportConfig},
+    ClientHandle,
+    Message,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+    time::timeout,
+};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+fn config(framing: Framing) -> TransportConfig {
+    TransportConfig {
+        framing,
+        backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(50), 2),
+        ..TransportConfig::default()
+    }
+}
+
+async fn next_event(events: &mut broadcast::Receiver<ClientEvent>) -> ClientEvent {
+    timeout(WAIT, events.recv()).await.expect("event in time").expect("event")
+}
+
+async fn accept(listener: &TcpListener) -> BufReader<TcpStream> {
+    let (stream, _) =
+        timeout(WAIT, listener.accept()).await.expect("connection in time").expect("accept");
+    BufReader::new(stream)
+}
+
+async fn read_frame(stream: &mut BufReader<TcpStream>, framing: Framing) -> String {
+    match framing {
+        Framing::LengthDelimited => {
+            let len = stream.read_u32().await.expect("frame length") as usize;
+            let mut buffer = vec![0; len];
+            stream.read_exact(&mut buffer).await.expect("frame");
+            String::from_utf8(buffer).expect("utf-8 frame")
+        },
+        Framing::Newline => {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.expect("frame");
+            line.trim_end_matches('\n').to_string()
+        },
+    }
+}
+
+async fn write_frame(stream: &mut BufReader<TcpStream>, framing: Framing, message: &str) {
+    let stream = stream.get_mut();
+    match framing {
+        Framing::LengthDelimited => {
+            stream.write_u32(message.len() as u32).await.expect("frame length");
+            stream.write_all(message.as_bytes()).await.expect("frame");
+        },
+        Framing::Newline => {
+            stream.write_all(format!("{}\r\n", message).as_bytes()).await.expect("frame");
+        },
+    }
+}
+
+async fn round_trip_and_reconnect(framing: Framing) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local address").to_string();
+    let peer = format!("tcp:{}", addr);
+    let (mut client, mut messages, mut events) =
+        ClientHandle::tcp(&tokio::runtime::Handle::current(), addr, config(framing))
+            .expect("client");
+
+    let mut stream = accept(&listener).await;
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Connected(peer.clone())
+    );
+
+    client.send_msg(Message::new("to the peer")).expect("send");
+    assert_eq!(read_frame(&mut stream, framing).await, "to the peer");
+    write_frame(&mut stream, framing, "from the peer").await;
+    let received = timeout(WAIT, messages.recv()).await.expect("message in time").expect("message");
+    assert_eq!(received.message, "from the peer");
+
+    drop(stream);
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Disconnected(peer.clone())
+    );
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Reconnecting(peer.clone())
+    );
+
+    // sent while disconnected, delivered once connected again
+    client.send_msg(Message::new("kept while away")).expect("send");
+    let mut stream = accept(&listener).await;
+    assert_eq!(next_event(&mut events).await, ClientEvent::Connected(peer));
+    assert_eq!(read_frame(&mut stream, framing).await, "kept while away");
+}
+
+#[tokio::test]
+async fn tcp_length_delimited() {
+    round_trip_and_reconnect(Framing::LengthDelimited).await;
+}
+
+#[tokio::test]
+async fn tcp_newline() {
+    round_trip_and_reconnect(Framing::Newline).await;
+}
+
+async fn dropped_then_next_delivered(framing: Framing, dropped: Message) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local address").to_string();
+    let (mut client, _, mut events) =
+        ClientHandle::tcp(&tokio::runtime::Handle::current(), addr, config(framing))
+            .expect("client");
+    let mut stream = accept(&listener).await;
+    assert!(matches!(next_event(&mut events).await, ClientEvent::Connected(_)));
+
+    client.send_msg(dropped).expect("send");
+    client.send_msg(Message::new("after it")).expect("send");
+    assert_eq!(read_frame(&mut stream, framing).await, "after it");
+}
+
+#[tokio::test]
+async fn tcp_oversize_message_is_dropped() {
+    let oversize = Message::new("x".repeat(8 * 1024 * 1024 + 1));
+    dropped_then_next_delivered(Framing::LengthDelimited, oversize).await;
+}
+
+#[tokio::test]
+async fn tcp_multiline_message_is_dropped() {
+    let multiline = Message::new("first line\nsecond line");
+    dropped_then_next_delivered(Framing::Newline, multiline).await;
+}
+
+#[test]
+fn backoff_delays_stay_above_zero() {
+    let mut backoff = Backoff::new(Duration::ZERO, Duration::ZERO, 2);
+    for _ in 0..3 {
+        assert!(backoff.next_delay() > Duration::ZERO);
+    }
+}
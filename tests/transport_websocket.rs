@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use rust_py::{
+    transport::{Backoff, ClientEvent, TransportConfig},
+    ClientHandle,
+    Message,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+    time::timeout,
+};
+use tokio_tungstenite::{accept_async, tungstenite::Message as WsMessage, WebSocketStream};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+async fn next_event(events: &mut broadcast::Receiver<ClientEvent>) -> ClientEvent {
+    timeout(WAIT, events.recv()).await.expect("event in time").expect("event")
+}
+
+async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+    let (stream, _) =
+        timeout(WAIT, listener.accept()).await.expect("connection in time").expect("accept");
+    accept_async(stream).await.expect("handshake")
+}
+
+async fn next_text(stream: &mut WebSocketStream<TcpStream>) -> String {
+    loop {
+        match timeout(WAIT, stream.next()).await.expect("frame in time") {
+            Some(Ok(WsMessage::Text(text))) => return text,
+            Some(Ok(_)) => continue,
+            other => panic!("expected a text frame, got {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn websocket_round_trip_and_reconnect() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let url = format!(
+        "ws://{}/feed",
+        listener.local_addr().expect("local address")
+    );
+    let config = TransportConfig {
+        backoff: Backoff::new(Duration::from_millis(10), Duration::from_millis(50), 2),
+        ..TransportConfig::default()
+    };
+    let (mut client, mut messages, mut events) =
+        ClientHandle::websocket(&tokio::runtime::Handle::current(), url.clone(), config)
+            .expect("client");
+
+    let mut stream = accept(&listener).await;
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Connected(url.clone())
+    );
+
+    client.send_msg(Message::new("to the server")).expect("send");
+    assert_eq!(next_text(&mut stream).await, "to the server");
+    stream.send(WsMessage::Text(String::from("as text"))).await.expect("text frame");
+    stream.send(WsMessage::Binary(b"as binary".to_vec())).await.expect("binary frame");
+    for expected in ["as text", "as binary"] {
+        let received =
+            timeout(WAIT, messages.recv()).await.expect("message in time").expect("message");
+        assert_eq!(received.message, expected);
+    }
+
+    stream.close(None).await.expect("close");
+    drop(stream);
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Disconnected(url.clone())
+    );
+    assert_eq!(
+        next_event(&mut events).await,
+        ClientEvent::Reconnecting(url.clone())
+    );
+
+    // sent while disconnected, delivered once connected again
+    client.send_msg(Message::new("kept while away")).expect("send");
+    let mut stream = accept(&listener).await;
+    assert_eq!(next_event(&mut events).await, ClientEvent::Connected(url));
+    assert_eq!(next_text(&mut stream).await, "kept while away");
+}